@@ -2,7 +2,8 @@ use super::{slice::DataReaderSlice, DataReadBuf, View};
 use std::{
     borrow::{Borrow, BorrowMut},
     marker::PhantomData,
-    mem::{transmute, MaybeUninit},
+    mem::{self, transmute, MaybeUninit},
+    slice,
 };
 
 #[derive(Debug, Clone)]
@@ -27,7 +28,8 @@ impl<T> DataReadBufImpl<T, [MaybeUninit<T>; 10]> {
 }
 
 impl<T, C: BorrowMut<[MaybeUninit<T>]>> DataReadBufImpl<T, C> {
-    /// Safety: Caller must make sure the slice returned by C must be initialized correctly up to `inited`!
+    /// # Safety
+    /// Caller must make sure the slice returned by C must be initialized correctly up to `inited`!
     pub unsafe fn new_unchecked(data: C, filled: usize, inited: usize) -> Self {
         Self {
             data,
@@ -46,10 +48,7 @@ impl<'a, T> DataReadBufImpl<T, &'a mut [MaybeUninit<T>]> {
     }
 }
 
-impl<T, C: BorrowMut<[MaybeUninit<T>]>> View for DataReadBufImpl<T, C>
-where
-    T: Clone,
-{
+impl<T, C: BorrowMut<[MaybeUninit<T>]>> View for DataReadBufImpl<T, C> {
     unsafe fn set_init(&mut self, value: usize) {
         self.inited = value
     }
@@ -71,15 +70,52 @@ impl<T, C: BorrowMut<[MaybeUninit<T>]>> DataReadBufImpl<T, C> {
         self.filled += 1;
     }
 
-    pub fn take(&mut self, n: usize) -> DataReaderSlice<'_, Self>
-    where
-        T: Clone,
-    {
+    /// Move the filled items out, leaving the buffer empty
+    pub fn drain(&mut self) -> Drain<'_, T> {
+        let filled = mem::take(&mut self.filled);
+        let inited = mem::take(&mut self.inited);
+        let data = &mut self.data.borrow_mut()[..inited];
+        // Initialized past the filled ones but never handed out
+        for x in &mut data[filled..] {
+            unsafe { x.assume_init_drop() }
+        }
+
+        Drain {
+            iter: data[..filled].iter_mut(),
+        }
+    }
+
+    pub fn take(&mut self, n: usize) -> DataReaderSlice<'_, Self> {
         self.inited = self.filled;
+        let n = n.min(self.data.borrow().len() - self.filled);
         DataReaderSlice::new(self, self.filled, n)
     }
 }
 
+/// Filled items moved out of a [`DataReadBufImpl`], the ones not taken are dropped with it. Create
+/// with [`DataReadBufImpl::drain`]
+#[derive(Debug)]
+pub struct Drain<'a, T> {
+    iter: slice::IterMut<'a, MaybeUninit<T>>,
+}
+
+impl<T> Iterator for Drain<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        // Safety: the buffer forgot these items when the drain started, each is read once
+        self.iter.next().map(|x| unsafe { x.assume_init_read() })
+    }
+}
+
+impl<T> Drop for Drain<'_, T> {
+    fn drop(&mut self) {
+        for x in &mut self.iter {
+            unsafe { x.assume_init_drop() }
+        }
+    }
+}
+
 impl<T, C: BorrowMut<[MaybeUninit<T>]>> Drop for DataReadBufImpl<T, C> {
     fn drop(&mut self) {
         for x in &mut self.data.borrow_mut()[..self.inited] {
@@ -90,7 +126,6 @@ impl<T, C: BorrowMut<[MaybeUninit<T>]>> Drop for DataReadBufImpl<T, C> {
 
 impl<T, C> DataReadBuf for DataReadBufImpl<T, C>
 where
-    T: Clone,
     C: BorrowMut<[MaybeUninit<T>]> + Borrow<[MaybeUninit<T>]>,
 {
     type Item = T;
@@ -109,18 +144,26 @@ where
         unsafe { transmute(data) }
     }
 
+    fn push(&mut self, item: T) {
+        match self.unfilled_mut().first_mut() {
+            Some(x) => {
+                *x = item;
+                self.filled += 1;
+            }
+            None => self.push_uninit(item),
+        }
+    }
+
     // Note: Item are cloned into buffer, if you need to preserve reference semantics, use Rc or Arc
-    fn put_slice(&mut self, data: &[T]) {
+    fn put_slice(&mut self, data: &[T])
+    where
+        T: Clone,
+    {
         let uf = self.unfilled_mut();
         let mut iter = data.iter();
-        let mut uf_iter = uf.iter_mut();
         let mut written = 0;
 
-        loop {
-            let Some(ufn) = uf_iter.next() else {
-                break;
-            };
-
+        for ufn in uf.iter_mut() {
             let Some(item) = iter.next() else {
                 self.filled += written;
                 return;
@@ -168,7 +211,7 @@ pub mod tests {
         let mut buf = DataReadBufImpl::slice_uninit(inner.as_mut_slice());
 
         let data = Rc::new(0);
-        buf.put_slice(&[data.clone()]);
+        buf.put_slice(std::slice::from_ref(&data));
 
         assert_eq!(Rc::strong_count(&data), 2);
         drop(buf);
@@ -185,6 +228,20 @@ pub mod tests {
         assert_eq!(buf.filled_mut(), &mut [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
     }
 
+    #[test]
+    fn drain() {
+        let mut buf = DataReadBufImpl::new_stack_alloc::<4>();
+        let items: Vec<_> = (0..3).map(Rc::new).collect();
+        buf.put_slice(&items);
+        buf.shrink(1);
+
+        let mut drain = buf.drain();
+        assert_eq!(drain.next().as_deref(), Some(&0));
+        drop(drain);
+        assert!(items.iter().all(|x| Rc::strong_count(x) == 1));
+        assert!(buf.filled().is_empty());
+    }
+
     #[test]
     fn take() {
         let mut buf = DataReadBufImpl::new_stack_alloc::<10>();
@@ -1,9 +1,10 @@
 pub mod default_impl;
 pub mod slice;
+pub mod staging;
 
 use std::mem::MaybeUninit;
 
-pub use self::{default_impl::DataReadBufImpl, slice::DataReaderSlice};
+pub use self::{default_impl::DataReadBufImpl, slice::DataReaderSlice, staging::Staging};
 
 /// Create new owned buffer with [`new`] or [`new_boxed`]
 pub trait DataReadBuf {
@@ -13,17 +14,36 @@ pub trait DataReadBuf {
     fn shrink(&mut self, count: usize);
     fn capacity(&self) -> usize;
     fn filled_mut(&mut self) -> &mut [Self::Item];
+    /// View of the next `n` unfilled items, cut to what is left
     fn take(&mut self, n: usize) -> impl DataReadBuf<Item = Self::Item> + '_
     where
         Self: Sized;
 
+    /// Put one item, moving it into the buffer
+    fn push(&mut self, item: Self::Item);
+
     // Same req as tokio ReadBuf
-    fn put_slice(&mut self, data: &[Self::Item]);
-    fn put_slice_guard(&mut self, data: &[Self::Item]) -> usize {
+    fn put_slice(&mut self, data: &[Self::Item])
+    where
+        Self::Item: Clone;
+    fn put_slice_guard(&mut self, data: &[Self::Item]) -> usize
+    where
+        Self::Item: Clone,
+    {
         let remaining = self.capacity() - self.filled().len();
         let writable = remaining.min(data.len());
         self.put_slice(&data[..writable]);
-        return writable;
+        writable
+    }
+
+    /// Put `n` copies of `item`
+    fn put_fill(&mut self, item: &Self::Item, n: usize)
+    where
+        Self::Item: Clone,
+    {
+        for _ in 0..n {
+            self.put_slice(std::slice::from_ref(item));
+        }
     }
 }
 
@@ -42,6 +62,8 @@ where
 }
 
 pub trait View: DataReadBuf {
+    /// # Safety
+    /// The first `value` items must be initialized
     unsafe fn set_init(&mut self, value: usize);
     fn set_filled(&mut self, value: usize);
 }
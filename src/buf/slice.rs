@@ -49,22 +49,30 @@ impl<'p, P: View> DataReadBuf for DataReaderSlice<'p, P> {
     }
 
     fn shrink(&mut self, count: usize) {
-        debug_assert!(
-            self.filled().len() - self.pos >= count,
-            "Shrink more than filled!"
-        );
+        debug_assert!(self.filled().len() >= count, "Shrink more than filled!");
         self.parent.shrink(count);
     }
 
     fn take(&mut self, n: usize) -> impl DataReadBuf<Item = Self::Item> {
         DataReaderSlice {
-            len: n,
+            len: n.min(self.capacity() - self.filled().len()),
             pos: self.filled().len(),
             parent: self,
         }
     }
 
-    fn put_slice(&mut self, data: &[Self::Item]) {
+    fn push(&mut self, item: Self::Item) {
+        assert!(
+            self.capacity() > self.filled().len(),
+            "data overflow buffer!"
+        );
+        self.parent.push(item);
+    }
+
+    fn put_slice(&mut self, data: &[Self::Item])
+    where
+        Self::Item: Clone,
+    {
         let unfilled = self.capacity() - self.filled().len();
         assert!(unfilled >= data.len(), "data overflow buffer!");
         self.parent.put_slice(data);
@@ -79,12 +87,13 @@ pub mod tests {
     fn take() {
         let mut buf = DataReadBufImpl::new_stack_alloc::<10>();
         buf.put_slice(&[1, 2, 3, 4, 5]);
-        let mut slice = buf.take(4);
-        slice.put_slice(&[6]);
-        assert_eq!(slice.filled(), &[6]);
-        assert_eq!(slice.filled_mut(), &mut [6]);
+        {
+            let mut slice = buf.take(4);
+            slice.put_slice(&[6]);
+            assert_eq!(slice.filled(), &[6]);
+            assert_eq!(slice.filled_mut(), &mut [6]);
+        }
 
-        drop(slice);
         assert_eq!(buf.filled(), &[1, 2, 3, 4, 5, 6])
     }
 }
@@ -0,0 +1,27 @@
+use std::mem::MaybeUninit;
+
+use super::DataReadBufImpl;
+
+/// Scratch space for readers that read items themselves before handing them to the caller
+#[derive(Debug)]
+pub struct Staging<T> {
+    data: Box<[MaybeUninit<T>]>,
+}
+
+impl<T> Default for Staging<T> {
+    fn default() -> Self {
+        Self { data: Box::new([]) }
+    }
+}
+
+impl<T> Staging<T> {
+    /// Empty buffer with a capacity of `n`. Items left in it are dropped with the returned buffer,
+    /// so the storage is uninitialized again between uses
+    pub fn get(&mut self, n: usize) -> DataReadBufImpl<T, &mut [MaybeUninit<T>]> {
+        if self.data.len() < n {
+            self.data = (0..n).map(|_| MaybeUninit::uninit()).collect();
+        }
+
+        DataReadBufImpl::slice_uninit(&mut self.data[..n])
+    }
+}
@@ -3,3 +3,23 @@ pub enum Or<L, R> {
     L(L),
     R(R),
 }
+
+impl<L, R> Or<L, R> {
+    /// Transform the left value, keeping the right one
+    pub fn map_l<T>(self, f: impl FnOnce(L) -> T) -> Or<T, R> {
+        match self {
+            Or::L(x) => Or::L(f(x)),
+            Or::R(x) => Or::R(x),
+        }
+    }
+}
+
+impl<L, R> Or<Or<L, R>, R> {
+    /// Merge the two places an `R` can come from
+    pub fn flatten(self) -> Or<L, R> {
+        match self {
+            Or::L(Or::L(x)) => Or::L(x),
+            Or::L(Or::R(x)) | Or::R(x) => Or::R(x),
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    io::{self, SeekFrom},
+    ops::Range,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use pin_project::pin_project;
+use tokio::io::AsyncSeek;
+
+use super::{
+    provenance::{LayerPath, Provenance},
+    AsyncDataRead,
+};
+use crate::{
+    buf::{self, DataReadBuf, Staging},
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
+
+/// Which bit of a byte comes first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitOrder {
+    MsbFirst,
+    LsbFirst,
+}
+
+impl BitOrder {
+    fn bit(self, byte: u8, i: u64) -> bool {
+        let shift = match self {
+            BitOrder::MsbFirst => 7 - i,
+            BitOrder::LsbFirst => i,
+        };
+        (byte >> shift) & 1 == 1
+    }
+}
+
+/// Read a byte reader bit by bit, position `p` is bit `p % 8` of byte `p / 8`. Create with
+/// [`AsyncDataRead::bits`]
+///
+/// A hole in the bytes is 8 bits of hole. Shifts and limits on top of this count bits.
+#[derive(Debug)]
+#[pin_project]
+pub struct Bits<R> {
+    #[pin]
+    reader: R,
+    inner: InnerCursor,
+    order: BitOrder,
+    stage: Staging<u8>,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+impl<R> Bits<R> {
+    pub fn new(reader: R, order: BitOrder) -> Self {
+        Self {
+            reader,
+            inner: InnerCursor::default(),
+            order,
+            stage: Staging::default(),
+            cur: 0,
+            seek_op: None,
+        }
+    }
+
+    pub fn order(&self) -> BitOrder {
+        self.order
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R> Bits<R>
+where
+    R: AsyncDataRead<Item = u8> + AsyncSeek + Unpin,
+{
+    /// Unsigned field of `n` bits at bit `pos`, the first bit read is the most significant one
+    /// for [`BitOrder::MsbFirst`] and the least significant one otherwise. `None` if any of the
+    /// bits is missing
+    pub async fn read_field(
+        &mut self,
+        pos: u64,
+        n: u32,
+    ) -> Result<Option<u64>, Or<R::Err, io::Error>> {
+        assert!(n <= 64, "field wider than 64 bits!");
+        let mut buf = buf::new::<64, _>();
+        let mut field = buf.take(n as usize);
+        self.read(pos, &mut field).await.map_err(Or::flatten)?;
+        let bits = field.filled();
+        if bits.len() < n as usize {
+            return Ok(None);
+        }
+
+        let value = match self.order {
+            BitOrder::MsbFirst => bits.iter().fold(0, |acc, x| (acc << 1) | *x as u64),
+            BitOrder::LsbFirst => bits.iter().rev().fold(0, |acc, x| (acc << 1) | *x as u64),
+        };
+        Ok(Some(value))
+    }
+}
+
+impl<R> AsyncDataRead for Bits<R>
+where
+    R: AsyncDataRead<Item = u8> + AsyncSeek,
+{
+    type Item = bool;
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let mut this = self.project();
+        let cur = *this.cur;
+        let (byte, off) = (cur / 8, cur % 8);
+        let unfilled = (buf.capacity() - buf.filled().len()) as u64;
+        if unfilled == 0 {
+            return Poll::Ready(Ok(Some(cur)));
+        }
+
+        ready!(this.inner.poll_seek(this.reader.as_mut(), cx, byte)).map_err(Or::R)?;
+        let mut stage = this.stage.get((off + unfilled).div_ceil(8) as usize);
+        let next = ready!(this.reader.poll_read(cx, &mut stage)).map_err(Or::L)?;
+        let bytes = stage.filled();
+        this.inner.advance(bytes.len() as u64);
+        if bytes.is_empty() {
+            return Poll::Ready(Ok(next.map(|x| x * 8)));
+        }
+
+        let end = (off + unfilled).min(bytes.len() as u64 * 8);
+        for i in off..end {
+            let bit = this.order.bit(bytes[(i / 8) as usize], i % 8);
+            buf.put_slice(&[bit]);
+        }
+        *this.cur += end - off;
+        Poll::Ready(Ok(Some(*this.cur)))
+    }
+}
+
+impl<R: AsyncSeek> AsyncSeek for Bits<R> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        *self.project().seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.project();
+        let Some(op) = *this.seek_op else {
+            return Poll::Ready(Ok(*this.cur));
+        };
+
+        let len = match op {
+            SeekFrom::End(_) => ready!(this.inner.poll_len(this.reader, cx))? * 8,
+            _ => 0,
+        };
+        *this.seek_op = None;
+        *this.cur = op.eval(*this.cur, len)?;
+        Poll::Ready(Ok(*this.cur))
+    }
+}
+
+impl<R: Provenance> Provenance for Bits<R> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        let bytes = range.start / 8..range.end.div_ceil(8);
+        self.reader
+            .provenance(bytes)
+            .into_iter()
+            .map(|(r, path)| {
+                let r = (r.start * 8).max(range.start)..(r.end * 8).min(range.end);
+                (r, path)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BitOrder;
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    #[tokio::test]
+    async fn bits() {
+        let mut source = OverlayOnce::new([0b1010_0000u8, 0b0000_0001]).bits(BitOrder::MsbFirst);
+        let mut buf = buf::new::<4, _>();
+        let next = source
+            .read_single_pass(6, &mut buf)
+            .await
+            .expect("Read failed!");
+
+        assert_eq!(next, Some(10));
+        assert_eq!(buf.filled(), &[false, false, false, false]);
+
+        let field = source.read_field(0, 3).await.expect("Read failed!");
+        assert_eq!(field, Some(0b101));
+    }
+
+    #[tokio::test]
+    async fn lsb_field() {
+        let mut source = OverlayOnce::new([0b1100_0000u8, 0b0000_0011]).bits(BitOrder::LsbFirst);
+        let field = source.read_field(6, 4).await.expect("Read failed!");
+        assert_eq!(field, Some(0b1111));
+
+        let field = source.read_field(12, 8).await.expect("Read failed!");
+        assert_eq!(field, None);
+    }
+}
@@ -0,0 +1,245 @@
+use std::{
+    io::{self, SeekFrom},
+    ops::Range,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use pin_project::pin_project;
+use tokio::io::AsyncSeek;
+
+use super::{
+    provenance::{LayerPath, Provenance},
+    AsyncDataRead,
+};
+use crate::{
+    buf::{DataReadBuf, Staging},
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
+
+/// A transform that works on whole blocks, like a seekable cipher or a sector scrambler
+pub trait BlockTransform {
+    type Item;
+
+    fn block_size(&self) -> usize;
+
+    /// Transform block `index` in place. The last block of a reader can be shorter
+    fn transform(&mut self, index: u64, block: &mut [Self::Item]);
+}
+
+/// XOR every block with a keystream generated from its index
+#[derive(Debug, Clone)]
+pub struct XorKeystream<F> {
+    block_size: usize,
+    keystream: F,
+    key: Vec<u8>,
+}
+
+impl<F: FnMut(u64, &mut [u8])> XorKeystream<F> {
+    /// `keystream` fills the key for a block index, always `block_size` long
+    pub fn new(block_size: usize, keystream: F) -> Self {
+        assert!(block_size > 0, "empty blocks!");
+        Self {
+            block_size,
+            keystream,
+            key: vec![0; block_size],
+        }
+    }
+}
+
+impl<F: FnMut(u64, &mut [u8])> BlockTransform for XorKeystream<F> {
+    type Item = u8;
+
+    fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    fn transform(&mut self, index: u64, block: &mut [u8]) {
+        (self.keystream)(index, &mut self.key);
+        for (x, k) in block.iter_mut().zip(&self.key) {
+            *x ^= k;
+        }
+    }
+}
+
+/// Whole blocks being read for one read
+#[derive(Debug)]
+struct Fetch<T> {
+    start: u64,
+    end: u64,
+    data: Vec<T>,
+    /// Set once a read found a hole after `data`
+    stop: Option<Option<u64>>,
+}
+
+/// Read through a [`BlockTransform`]. Create with [`AsyncDataRead::block_transform`]
+///
+/// Reads fetch every block they touch in full. A block cut short by a hole is a hole as a whole,
+/// only the last block of the reader can be short.
+#[derive(Debug)]
+#[pin_project]
+pub struct Blocks<R: AsyncDataRead, X> {
+    #[pin]
+    reader: R,
+    inner: InnerCursor,
+    transform: X,
+    fetch: Option<Fetch<R::Item>>,
+    stage: Staging<R::Item>,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+impl<R: AsyncDataRead, X: BlockTransform<Item = R::Item>> Blocks<R, X> {
+    pub fn new(reader: R, transform: X) -> Self {
+        assert!(transform.block_size() > 0, "empty blocks!");
+        Self {
+            reader,
+            inner: InnerCursor::default(),
+            transform,
+            fetch: None,
+            stage: Staging::default(),
+            cur: 0,
+            seek_op: None,
+        }
+    }
+
+    pub fn into_inner(self) -> (R, X) {
+        (self.reader, self.transform)
+    }
+}
+
+impl<R, X> AsyncDataRead for Blocks<R, X>
+where
+    R: AsyncDataRead + AsyncSeek,
+    R::Item: Clone,
+    X: BlockTransform<Item = R::Item>,
+{
+    type Item = R::Item;
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let mut this = self.project();
+        let size = this.transform.block_size() as u64;
+        let cur = *this.cur;
+        let unfilled = (buf.capacity() - buf.filled().len()) as u64;
+        if unfilled == 0 {
+            return Poll::Ready(Ok(Some(cur)));
+        }
+
+        let fetch = this.fetch.get_or_insert_with(|| Fetch {
+            start: cur - cur % size,
+            end: (cur + unfilled).div_ceil(size) * size,
+            data: Vec::new(),
+            stop: None,
+        });
+
+        while fetch.stop.is_none() && fetch.start + (fetch.data.len() as u64) < fetch.end {
+            let pos = fetch.start + fetch.data.len() as u64;
+            ready!(this.inner.poll_seek(this.reader.as_mut(), cx, pos)).map_err(Or::R)?;
+            let mut stage = this.stage.get((fetch.end - pos) as usize);
+            let next = match ready!(this.reader.as_mut().poll_read(cx, &mut stage)) {
+                Ok(x) => x,
+                Err(err) => {
+                    *this.fetch = None;
+                    return Poll::Ready(Err(Or::L(err)));
+                }
+            };
+
+            let data = stage.filled();
+            this.inner.advance(data.len() as u64);
+            if data.is_empty() {
+                fetch.stop = Some(next);
+            }
+            fetch.data.extend(data.iter().cloned());
+        }
+
+        let mut fetch = this.fetch.take().expect("Read in progress");
+        let valid = match fetch.stop {
+            // The last block may be short
+            Some(None) => fetch.data.len(),
+            _ => fetch.data.len() / size as usize * size as usize,
+        };
+        let first = fetch.start / size;
+        for (i, block) in fetch.data[..valid].chunks_mut(size as usize).enumerate() {
+            this.transform.transform(first + i as u64, block);
+        }
+
+        let off = (cur - fetch.start) as usize;
+        if valid > off {
+            let end = valid.min(off + unfilled as usize);
+            buf.put_slice(&fetch.data[off..end]);
+            *this.cur += (end - off) as u64;
+            return Poll::Ready(Ok(Some(*this.cur)));
+        }
+
+        // The block holding the next data is only whole if it starts there
+        let next = fetch.stop.flatten().map(|x| x.div_ceil(size) * size);
+        Poll::Ready(Ok(next))
+    }
+}
+
+impl<R: AsyncDataRead + AsyncSeek, X> AsyncSeek for Blocks<R, X> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.project();
+        *this.fetch = None;
+        *this.seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.project();
+        let Some(op) = *this.seek_op else {
+            return Poll::Ready(Ok(*this.cur));
+        };
+
+        let len = match op {
+            SeekFrom::End(_) => ready!(this.inner.poll_len(this.reader, cx))?,
+            _ => 0,
+        };
+        *this.seek_op = None;
+        *this.cur = op.eval(*this.cur, len)?;
+        Poll::Ready(Ok(*this.cur))
+    }
+}
+
+impl<R: AsyncDataRead + Provenance, X> Provenance for Blocks<R, X> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        self.reader.provenance(range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::XorKeystream;
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    #[tokio::test]
+    async fn xor_keystream() {
+        // Key of block i is all i
+        let cipher = XorKeystream::new(4, |i, key: &mut [u8]| key.fill(i as u8));
+        let mut source = OverlayOnce::new(vec![0u8; 10]).block_transform(cipher);
+        let mut buf = buf::new::<5, _>();
+        let next = source
+            .read_single_pass(3, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(8));
+        assert_eq!(buf.filled(), &[0, 1, 1, 1, 1]);
+
+        let mut buf = buf::new::<5, _>();
+        let next = source
+            .read_single_pass(8, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(10));
+        assert_eq!(buf.filled(), &[2, 2]);
+    }
+}
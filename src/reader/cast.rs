@@ -0,0 +1,173 @@
+use std::{
+    io::{self, SeekFrom},
+    marker::PhantomData,
+    ops::Range,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use pin_project::pin_project;
+use tokio::io::AsyncSeek;
+
+use super::{
+    provenance::{LayerPath, Provenance},
+    records::RecordCursor,
+    AsyncDataRead,
+};
+use crate::{buf::DataReadBuf, or::Or};
+
+/// Byte order of the numbers read by [`Cast`]
+pub trait Endian {
+    fn decode<T: Numeric>(bytes: &[u8]) -> T;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum LittleEndian {}
+
+#[derive(Debug, Clone, Copy)]
+pub enum BigEndian {}
+
+impl Endian for LittleEndian {
+    fn decode<T: Numeric>(bytes: &[u8]) -> T {
+        T::from_le(bytes)
+    }
+}
+
+impl Endian for BigEndian {
+    fn decode<T: Numeric>(bytes: &[u8]) -> T {
+        T::from_be(bytes)
+    }
+}
+
+/// Numbers that can be read from their bytes
+pub trait Numeric: Copy {
+    const SIZE: usize;
+
+    /// `bytes` is exactly [`Self::SIZE`] long
+    fn from_le(bytes: &[u8]) -> Self;
+    fn from_be(bytes: &[u8]) -> Self;
+}
+
+macro_rules! numeric {
+    ($($t:ty),*) => {
+        $(
+            impl Numeric for $t {
+                const SIZE: usize = std::mem::size_of::<$t>();
+
+                fn from_le(bytes: &[u8]) -> Self {
+                    <$t>::from_le_bytes(bytes.try_into().expect("Wrong number size"))
+                }
+
+                fn from_be(bytes: &[u8]) -> Self {
+                    <$t>::from_be_bytes(bytes.try_into().expect("Wrong number size"))
+                }
+            }
+        )*
+    };
+}
+
+numeric!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+/// Read bytes as an array of numbers, item `i` is made from the `T::SIZE` bytes at
+/// `i * T::SIZE`. Create with [`AsyncDataRead::cast`]
+///
+/// A number with any of its bytes in a hole is a hole, so is a trailing partial number. For an
+/// array that doesn't start at byte 0, [`shift_left`](AsyncDataRead::shift_left) the bytes first.
+#[derive(Debug)]
+#[pin_project]
+pub struct Cast<R, T, E> {
+    #[pin]
+    reader: R,
+    cursor: RecordCursor<u8>,
+    _p: PhantomData<fn() -> (T, E)>,
+}
+
+impl<R, T: Numeric, E: Endian> Cast<R, T, E> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            cursor: RecordCursor::new(T::SIZE as u64),
+            _p: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R, T, E> AsyncDataRead for Cast<R, T, E>
+where
+    R: AsyncDataRead<Item = u8> + AsyncSeek,
+    T: Numeric,
+    E: Endian,
+{
+    type Item = T;
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let this = self.project();
+        let n = (buf.capacity() - buf.filled().len()) as u64;
+        this.cursor.poll_read(this.reader, cx, n, |bytes| {
+            buf.put_slice(&[E::decode(bytes)])
+        })
+    }
+}
+
+impl<R, T, E> AsyncSeek for Cast<R, T, E>
+where
+    R: AsyncDataRead<Item = u8> + AsyncSeek,
+{
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        self.project().cursor.start_seek(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.project();
+        this.cursor.poll_complete(this.reader, cx)
+    }
+}
+
+impl<R, T, E> Provenance for Cast<R, T, E>
+where
+    R: AsyncDataRead<Item = u8> + Provenance,
+{
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        self.cursor.provenance(&self.reader, range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BigEndian, LittleEndian};
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    #[tokio::test]
+    async fn cast() {
+        let data = vec![1u8, 0, 0, 0, 0, 0, 0, 2, 9];
+        let mut source = OverlayOnce::new(data.clone()).cast::<u32, LittleEndian>();
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(2));
+        assert_eq!(buf.filled(), &[1, 0x0200_0000]);
+
+        let mut source = OverlayOnce::new(data).cast::<i16, BigEndian>();
+        let mut buf = buf::new::<10, _>();
+        source
+            .read_single_pass(3, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(buf.filled(), &[2]);
+    }
+}
@@ -0,0 +1,449 @@
+use std::{
+    io::{self, SeekFrom},
+    ops::Range,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use pin_project::pin_project;
+use tokio::io::AsyncSeek;
+
+use super::{
+    provenance::{under, Layer, LayerPath, Provenance},
+    AsyncDataRead,
+};
+use crate::{
+    buf::DataReadBuf,
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
+
+/// Read the reader placed at `part`, `cur` is in chain coordinates
+fn poll_part<R, B>(
+    reader: Pin<&mut R>,
+    inner: &mut InnerCursor,
+    cx: &mut Context<'_>,
+    buf: &mut B,
+    cur: u64,
+    part: Range<u64>,
+    last: bool,
+) -> Poll<Result<(u64, Option<u64>), Or<R::Err, io::Error>>>
+where
+    R: AsyncDataRead + AsyncSeek,
+    B: DataReadBuf<Item = R::Item>,
+{
+    let mut reader = reader;
+    let (start, len) = (part.start, part.end - part.start);
+    let off = cur - start;
+    ready!(inner.poll_seek(reader.as_mut(), cx, off)).map_err(Or::R)?;
+
+    let unfilled = (buf.capacity() - buf.filled().len()) as u64;
+    let mut new_buf = buf.take(unfilled.min(len - off) as usize);
+    let next = ready!(reader.poll_read(cx, &mut new_buf)).map_err(Or::L)?;
+    let wb = new_buf.filled().len() as u64;
+    inner.advance(wb);
+    if wb > 0 {
+        return Poll::Ready(Ok((wb, Some(cur + wb))));
+    }
+
+    // The hole may run into the next part, which is the earliest place data can come back
+    let next = match next {
+        Some(x) if x < len => Some(start + x),
+        _ if !last => Some(part.end),
+        _ => None,
+    };
+    Poll::Ready(Ok((0, next)))
+}
+
+/// Place `second` right after `first` ends. Create with [`AsyncDataRead::chain`] or
+/// [`AsyncDataRead::chain_sized`]
+#[derive(Debug)]
+#[pin_project]
+pub struct Chain<A, B> {
+    #[pin]
+    first: A,
+    #[pin]
+    second: B,
+    first_cur: InnerCursor,
+    second_cur: InnerCursor,
+    len: Option<u64>,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+impl<A, B> Chain<A, B> {
+    /// `len` is where `second` starts, `None` to use the length of `first`
+    pub fn new(first: A, second: B, len: Option<u64>) -> Self {
+        Self {
+            first,
+            second,
+            first_cur: InnerCursor::default(),
+            second_cur: InnerCursor::default(),
+            len,
+            cur: 0,
+            seek_op: None,
+        }
+    }
+
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<A, B> AsyncDataRead for Chain<A, B>
+where
+    A: AsyncDataRead + AsyncSeek,
+    B: AsyncDataRead<Item = A::Item> + AsyncSeek,
+{
+    type Item = A::Item;
+    type Err = Or<Or<A::Err, B::Err>, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let mut this = self.project();
+        let len = match *this.len {
+            Some(x) => x,
+            None => {
+                let len =
+                    ready!(this.first_cur.poll_len(this.first.as_mut(), cx)).map_err(Or::R)?;
+                *this.len = Some(len);
+                len
+            }
+        };
+
+        let cur = *this.cur;
+        let (wb, next) = if cur < len {
+            let part = poll_part(this.first, this.first_cur, cx, buf, cur, 0..len, false);
+            ready!(part).map_err(|e| e.map_l(Or::L))?
+        } else {
+            let part = poll_part(
+                this.second,
+                this.second_cur,
+                cx,
+                buf,
+                cur,
+                len..u64::MAX,
+                true,
+            );
+            ready!(part).map_err(|e| e.map_l(Or::R))?
+        };
+
+        *this.cur += wb;
+        Poll::Ready(Ok(next))
+    }
+}
+
+impl<A: AsyncSeek, B: AsyncSeek> AsyncSeek for Chain<A, B> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        *self.project().seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let mut this = self.project();
+        let Some(op) = *this.seek_op else {
+            return Poll::Ready(Ok(*this.cur));
+        };
+
+        let total = if let SeekFrom::End(_) = op {
+            let len = match *this.len {
+                Some(x) => x,
+                None => ready!(this.first_cur.poll_len(this.first.as_mut(), cx))?,
+            };
+            *this.len = Some(len);
+            len + ready!(this.second_cur.poll_len(this.second.as_mut(), cx))?
+        } else {
+            0
+        };
+
+        *this.seek_op = None;
+        *this.cur = op.eval(*this.cur, total)?;
+        Poll::Ready(Ok(*this.cur))
+    }
+}
+
+// Lengths that were not resolved by a read or seek yet count as unbounded
+impl<A: Provenance, B: Provenance> Provenance for Chain<A, B> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        let len = self.len.unwrap_or(u64::MAX);
+        let mut rs = Vec::new();
+        if range.start < len {
+            let spans = self.first.provenance(range.start..range.end.min(len));
+            rs.extend(under(Layer::Part(0), spans));
+        }
+
+        if range.end > len {
+            let spans = self
+                .second
+                .provenance(range.start.max(len) - len..range.end - len)
+                .into_iter()
+                .map(|(r, path)| (r.start + len..r.end + len, path))
+                .collect();
+            rs.extend(under(Layer::Part(1), spans));
+        }
+
+        rs
+    }
+}
+
+/// Place every reader right after the previous one ends. Create with [`concat`] or
+/// [`Concat::sized`]
+#[derive(Debug)]
+pub struct Concat<R> {
+    readers: Vec<R>,
+    cursors: Vec<InnerCursor>,
+    lens: Vec<Option<u64>>,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+/// Place `readers` end to end, each one starts where the previous one's length ends
+pub fn concat<R>(readers: impl IntoIterator<Item = R>) -> Concat<R> {
+    Concat::new(readers.into_iter().map(|x| (x, None)))
+}
+
+impl<R> Concat<R> {
+    /// `None` lengths are resolved by seeking the reader to its end
+    pub fn new(readers: impl IntoIterator<Item = (R, Option<u64>)>) -> Self {
+        let (readers, lens): (Vec<_>, Vec<_>) = readers.into_iter().unzip();
+        Self {
+            cursors: vec![InnerCursor::default(); readers.len()],
+            readers,
+            lens,
+            cur: 0,
+            seek_op: None,
+        }
+    }
+
+    pub fn sized(readers: impl IntoIterator<Item = (R, u64)>) -> Self {
+        Self::new(readers.into_iter().map(|(r, len)| (r, Some(len))))
+    }
+
+    pub fn into_inner(self) -> Vec<R> {
+        self.readers
+    }
+}
+
+impl<R: AsyncSeek + Unpin> Concat<R> {
+    fn poll_len_of(&mut self, cx: &mut Context<'_>, i: usize) -> Poll<io::Result<u64>> {
+        if let Some(len) = self.lens[i] {
+            return Poll::Ready(Ok(len));
+        }
+
+        let len = ready!(self.cursors[i].poll_len(Pin::new(&mut self.readers[i]), cx))?;
+        self.lens[i] = Some(len);
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl<R> AsyncDataRead for Concat<R>
+where
+    R: AsyncDataRead + AsyncSeek + Unpin,
+{
+    type Item = R::Item;
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let this = self.get_mut();
+        let count = this.readers.len();
+        let mut start: u64 = 0;
+        for i in 0..count {
+            let len = ready!(this.poll_len_of(cx, i)).map_err(Or::R)?;
+            let end = start.saturating_add(len);
+            if this.cur < end {
+                let reader = Pin::new(&mut this.readers[i]);
+                let cursor = &mut this.cursors[i];
+                let part = poll_part(
+                    reader,
+                    cursor,
+                    cx,
+                    buf,
+                    this.cur,
+                    start..end,
+                    i + 1 == count,
+                );
+                let (wb, next) = ready!(part)?;
+                this.cur += wb;
+                return Poll::Ready(Ok(next));
+            }
+
+            start = end;
+        }
+
+        Poll::Ready(Ok(None))
+    }
+}
+
+impl<R: AsyncSeek + Unpin> AsyncSeek for Concat<R> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        self.get_mut().seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        let Some(op) = this.seek_op else {
+            return Poll::Ready(Ok(this.cur));
+        };
+
+        let mut total = 0;
+        if let SeekFrom::End(_) = op {
+            for i in 0..this.readers.len() {
+                total += ready!(this.poll_len_of(cx, i))?;
+            }
+        }
+
+        this.seek_op = None;
+        this.cur = op.eval(this.cur, total)?;
+        Poll::Ready(Ok(this.cur))
+    }
+}
+
+// Lengths that were not resolved by a read or seek yet count as unbounded
+impl<R: Provenance> Provenance for Concat<R> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        let mut rs = Vec::new();
+        let mut start: u64 = 0;
+        for (i, (reader, len)) in self.readers.iter().zip(&self.lens).enumerate() {
+            let end = start.saturating_add(len.unwrap_or(u64::MAX));
+            let lo = range.start.max(start);
+            let hi = range.end.min(end);
+            if lo < hi {
+                let spans = reader
+                    .provenance(lo - start..hi - start)
+                    .into_iter()
+                    .map(|(r, path)| (r.start + start..r.end + start, path))
+                    .collect();
+                rs.extend(under(Layer::Part(i), spans));
+            }
+
+            start = end;
+        }
+
+        rs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, ErrorKind, SeekFrom},
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use pin_project::pin_project;
+    use tokio::io::AsyncSeek;
+
+    use super::{concat, Concat};
+    use crate::{
+        buf::{self, DataReadBuf},
+        or::Or,
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    /// Can't seek to its end, so its length is unknown
+    #[pin_project]
+    struct NoLen(#[pin] OverlayOnce<i32, Vec<i32>>);
+
+    impl AsyncDataRead for NoLen {
+        type Item = i32;
+        type Err = ();
+
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut impl DataReadBuf<Item = Self::Item>,
+        ) -> Poll<Result<Option<u64>, Self::Err>> {
+            self.project().0.poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncSeek for NoLen {
+        fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+            match position {
+                SeekFrom::End(_) => Err(ErrorKind::Unsupported.into()),
+                _ => self.project().0.start_seek(position),
+            }
+        }
+
+        fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+            self.project().0.poll_complete(cx)
+        }
+    }
+
+    #[tokio::test]
+    async fn chain() {
+        let mut source = OverlayOnce::new([1, 2, 3, 4]).chain(OverlayOnce::new([5, 6, 7]));
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(2, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(4));
+        assert_eq!(buf.filled(), &[3, 4]);
+
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(4, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(7));
+        assert_eq!(buf.filled(), &[5, 6, 7]);
+
+        let mut buf = buf::new::<10, _>();
+        let next = source.read(2, &mut buf).await.expect("Read failed!");
+        assert_eq!(next, None);
+        assert_eq!(buf.filled(), &[3, 4, 5, 6, 7]);
+    }
+
+    #[tokio::test]
+    async fn len_error() {
+        let first = NoLen(OverlayOnce::new(vec![1, 2]));
+        let mut source = first.chain(OverlayOnce::new(vec![3, 4]));
+        let mut buf = buf::new::<10, _>();
+        let rs = source.read(0, &mut buf).await.map_err(Or::flatten);
+        let Err(Or::R(err)) = rs else {
+            panic!("Length error expected!");
+        };
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+
+        let parts = [
+            NoLen(OverlayOnce::new(vec![1])),
+            NoLen(OverlayOnce::new(vec![2])),
+        ];
+        let mut source = concat(parts);
+        let rs = source.read(0, &mut buf).await.map_err(Or::flatten);
+        assert!(matches!(rs, Err(Or::R(_))));
+        assert!(buf.filled().is_empty());
+    }
+
+    #[tokio::test]
+    async fn concat_sized() {
+        let mut source = Concat::sized([
+            (OverlayOnce::new(vec![1, 2, 3, 4]), 6),
+            (OverlayOnce::new(vec![5, 6]), 2),
+        ]);
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(4, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(6));
+        assert_eq!(buf.filled(), &[]);
+
+        let next = source
+            .read_single_pass(6, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(8));
+        assert_eq!(buf.filled(), &[5, 6]);
+    }
+}
@@ -0,0 +1,487 @@
+use std::{
+    any::Any,
+    future::poll_fn,
+    io::{self, SeekFrom},
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use pin_project::pin_project;
+use tokio::io::AsyncSeek;
+
+use super::AsyncDataRead;
+use crate::{
+    buf::{DataReadBuf, Staging},
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
+
+/// Bytes read per step while decoding
+const CHUNK: usize = 4096;
+/// Chars between two remembered char boundaries
+const STRIDE: u64 = 1024;
+
+/// `err` as it is when it's an [`io::Error`], seeks can only fail with one
+fn into_io_error<E: 'static>(err: E) -> io::Error {
+    let err: Box<dyn Any> = Box::new(err);
+    match err.downcast::<io::Error>() {
+        Ok(err) => *err,
+        Err(_) => io::Error::other("Read failed while counting chars!"),
+    }
+}
+
+/// What a [`Chars`] reader does with bytes that aren't valid UTF-8. Every maximal invalid sequence
+/// takes up one char position, like [`String::from_utf8_lossy`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Utf8Policy {
+    /// Read as `U+FFFD`
+    #[default]
+    Replace,
+    /// Fail the read with [`InvalidUtf8`]
+    Error,
+    /// Read as a hole
+    Hole,
+}
+
+/// Invalid UTF-8 at char position `pos`, which starts at byte `byte`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidUtf8 {
+    pub pos: u64,
+    pub byte: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Char(char, usize),
+    Invalid(usize),
+    /// A hole in the bytes, one char position per byte. Data resumes at the given byte
+    Hole(Option<u64>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Decoded {
+    Unit(Unit),
+    Incomplete,
+}
+
+fn decode(bytes: &[u8]) -> Decoded {
+    let width = match bytes[0] {
+        0x00..=0x7f => 1,
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => return Decoded::Unit(Unit::Invalid(1)),
+    };
+
+    match std::str::from_utf8(&bytes[..width.min(bytes.len())]) {
+        Ok(s) => Decoded::Unit(Unit::Char(s.chars().next().expect("One char"), width)),
+        Err(err) => match err.error_len() {
+            Some(n) => Decoded::Unit(Unit::Invalid(n)),
+            None => Decoded::Incomplete,
+        },
+    }
+}
+
+/// Walks the bytes one char at a time, remembering the byte of every `STRIDE`-th char
+#[derive(Debug)]
+struct Decoder {
+    inner: InnerCursor,
+    stage: Staging<u8>,
+    /// Next char to decode and the byte it starts at
+    char: u64,
+    byte: u64,
+    /// Bytes read at `byte` but not decoded yet, starting at `off`
+    bytes: Vec<u8>,
+    off: usize,
+    /// What comes after `bytes` once a read found a hole there
+    tail: Option<Option<u64>>,
+    /// Byte of char `k * STRIDE`
+    marks: Vec<u64>,
+}
+
+impl Decoder {
+    fn new() -> Self {
+        Self {
+            inner: InnerCursor::default(),
+            stage: Staging::default(),
+            char: 0,
+            byte: 0,
+            bytes: Vec::new(),
+            off: 0,
+            tail: None,
+            marks: vec![0],
+        }
+    }
+
+    /// Move back, or ahead over what is already known, to the `k`-th mark
+    fn jump(&mut self, k: usize, back: bool) {
+        let k = k.min(self.marks.len() - 1);
+        let char = k as u64 * STRIDE;
+        if back || char > self.char {
+            self.char = char;
+            self.byte = self.marks[k];
+            self.bytes.clear();
+            self.off = 0;
+            self.tail = None;
+        }
+    }
+
+    fn note(&mut self) {
+        if self.char.is_multiple_of(STRIDE) && self.char / STRIDE == self.marks.len() as u64 {
+            self.marks.push(self.byte);
+        }
+    }
+
+    fn consume(&mut self, width: usize) {
+        self.off += width;
+        self.char += 1;
+        self.byte += width as u64;
+        self.note();
+    }
+
+    /// Skip `n` positions of the hole at `byte`
+    fn consume_hole(&mut self, mut n: u64, resume: Option<u64>) {
+        while n > 0 {
+            let step = n.min(STRIDE - self.char % STRIDE);
+            self.char += step;
+            self.byte += step;
+            n -= step;
+            self.note();
+        }
+
+        if Some(self.byte) >= resume {
+            self.tail = None;
+        }
+    }
+
+    /// The unit at `char`, reading more bytes if needed
+    fn poll_peek<R>(
+        &mut self,
+        mut reader: Pin<&mut R>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Unit, Or<R::Err, io::Error>>>
+    where
+        R: AsyncDataRead<Item = u8> + AsyncSeek,
+    {
+        loop {
+            let pending = &self.bytes[self.off..];
+            if !pending.is_empty() {
+                match decode(pending) {
+                    Decoded::Unit(x) => return Poll::Ready(Ok(x)),
+                    // Cut short by a hole or the end
+                    Decoded::Incomplete if self.tail.is_some() => {
+                        return Poll::Ready(Ok(Unit::Invalid(pending.len())))
+                    }
+                    Decoded::Incomplete => {}
+                }
+            } else if let Some(next) = self.tail {
+                return Poll::Ready(Ok(Unit::Hole(next.filter(|x| *x > self.byte))));
+            }
+
+            self.bytes.drain(..self.off);
+            self.off = 0;
+            let pos = self.byte + self.bytes.len() as u64;
+            ready!(self.inner.poll_seek(reader.as_mut(), cx, pos)).map_err(Or::R)?;
+            let mut stage = self.stage.get(CHUNK);
+            let next = ready!(reader.as_mut().poll_read(cx, &mut stage)).map_err(Or::L)?;
+            let data = stage.filled();
+            self.inner.advance(data.len() as u64);
+            if data.is_empty() {
+                self.tail = Some(next);
+            } else {
+                self.bytes.extend_from_slice(data);
+            }
+        }
+    }
+
+    /// Decode up to char `target` and return its byte, `None` if the bytes end before it
+    fn poll_to_char<R>(
+        &mut self,
+        mut reader: Pin<&mut R>,
+        cx: &mut Context<'_>,
+        target: u64,
+    ) -> Poll<Result<Option<u64>, Or<R::Err, io::Error>>>
+    where
+        R: AsyncDataRead<Item = u8> + AsyncSeek,
+    {
+        self.jump((target / STRIDE) as usize, self.char > target);
+        while self.char < target {
+            match ready!(self.poll_peek(reader.as_mut(), cx))? {
+                Unit::Char(_, w) | Unit::Invalid(w) => self.consume(w),
+                Unit::Hole(Some(x)) => {
+                    self.consume_hole((x - self.byte).min(target - self.char), Some(x))
+                }
+                Unit::Hole(None) => return Poll::Ready(Ok(None)),
+            }
+        }
+
+        Poll::Ready(Ok(Some(self.byte)))
+    }
+
+    /// Decode up to the char holding byte `target` and return it, `None` if the bytes end before
+    fn poll_to_byte<R>(
+        &mut self,
+        mut reader: Pin<&mut R>,
+        cx: &mut Context<'_>,
+        target: u64,
+    ) -> Poll<Result<Option<u64>, Or<R::Err, io::Error>>>
+    where
+        R: AsyncDataRead<Item = u8> + AsyncSeek,
+    {
+        let k = self.marks.partition_point(|x| *x <= target) - 1;
+        self.jump(k, self.byte > target);
+        loop {
+            let left = target - self.byte;
+            match ready!(self.poll_peek(reader.as_mut(), cx))? {
+                Unit::Char(_, w) | Unit::Invalid(w) if left >= w as u64 => self.consume(w),
+                Unit::Char(..) | Unit::Invalid(_) => return Poll::Ready(Ok(Some(self.char))),
+                Unit::Hole(Some(x)) if x - self.byte <= left => {
+                    self.consume_hole(x - self.byte, Some(x))
+                }
+                Unit::Hole(Some(_)) => return Poll::Ready(Ok(Some(self.char + left))),
+                Unit::Hole(None) => return Poll::Ready(Ok(None)),
+            }
+        }
+    }
+}
+
+/// Decode a byte reader as UTF-8, position `n` is the `n`-th char. Create with
+/// [`AsyncDataRead::chars`]
+///
+/// Holes in the bytes are holes of one char position per byte. The byte of every 1024th char is
+/// remembered, so reads far away from the last one only decode from the closest of them.
+#[derive(Debug)]
+#[pin_project]
+pub struct Chars<R> {
+    #[pin]
+    reader: R,
+    dec: Decoder,
+    policy: Utf8Policy,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+impl<R> Chars<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            dec: Decoder::new(),
+            policy: Utf8Policy::default(),
+            cur: 0,
+            seek_op: None,
+        }
+    }
+
+    pub fn policy(mut self, policy: Utf8Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R> Chars<R>
+where
+    R: AsyncDataRead<Item = u8> + AsyncSeek + Unpin,
+{
+    /// Byte where char `pos` starts, `None` past the end
+    pub async fn byte_pos(&mut self, pos: u64) -> Result<Option<u64>, Or<R::Err, io::Error>> {
+        poll_fn(|cx| self.dec.poll_to_char(Pin::new(&mut self.reader), cx, pos)).await
+    }
+
+    /// Char that byte `byte` is part of, `None` past the end
+    pub async fn char_pos(&mut self, byte: u64) -> Result<Option<u64>, Or<R::Err, io::Error>> {
+        poll_fn(|cx| self.dec.poll_to_byte(Pin::new(&mut self.reader), cx, byte)).await
+    }
+}
+
+impl<R> AsyncDataRead for Chars<R>
+where
+    R: AsyncDataRead<Item = u8> + AsyncSeek,
+{
+    type Item = char;
+    type Err = Or<Or<R::Err, InvalidUtf8>, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let mut this = self.project();
+        let cur = *this.cur;
+        let dec = this.dec;
+        if ready!(dec.poll_to_char(this.reader.as_mut(), cx, cur))
+            .map_err(|e| e.map_l(Or::L))?
+            .is_none()
+        {
+            return Poll::Ready(Ok(None));
+        }
+
+        let unfilled = buf.capacity() - buf.filled().len();
+        let mut written = 0;
+        while written < unfilled {
+            // Hand out what is decoded so far before waiting or failing
+            let unit = match dec.poll_peek(this.reader.as_mut(), cx) {
+                Poll::Ready(Ok(x)) => x,
+                _ if written > 0 => break,
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err.map_l(Or::L))),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            let c = match (unit, *this.policy) {
+                (Unit::Char(c, _), _) => c,
+                (Unit::Invalid(_), Utf8Policy::Replace) => char::REPLACEMENT_CHARACTER,
+                _ if written > 0 => break,
+                (Unit::Invalid(_), Utf8Policy::Error) => {
+                    let err = InvalidUtf8 {
+                        pos: cur,
+                        byte: dec.byte,
+                    };
+                    return Poll::Ready(Err(Or::L(Or::R(err))));
+                }
+                (Unit::Invalid(w), Utf8Policy::Hole) => {
+                    dec.consume(w);
+                    return Poll::Ready(Ok(Some(cur + 1)));
+                }
+                (Unit::Hole(next), _) => {
+                    return Poll::Ready(Ok(next.map(|x| cur + x - dec.byte)));
+                }
+            };
+
+            buf.put_slice(&[c]);
+            if let Unit::Char(_, w) | Unit::Invalid(w) = unit {
+                dec.consume(w);
+            }
+            written += 1;
+        }
+
+        *this.cur += written as u64;
+        Poll::Ready(Ok(Some(*this.cur)))
+    }
+}
+
+impl<R> AsyncSeek for Chars<R>
+where
+    R: AsyncDataRead<Item = u8> + AsyncSeek,
+    R::Err: 'static,
+{
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        *self.project().seek_op = Some(position);
+        Ok(())
+    }
+
+    // Seeking from the end decodes the whole reader to count its chars
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.project();
+        let Some(op) = *this.seek_op else {
+            return Poll::Ready(Ok(*this.cur));
+        };
+
+        let len = match op {
+            SeekFrom::End(_) => {
+                let rs = ready!(this.dec.poll_to_char(this.reader, cx, u64::MAX));
+                rs.map_err(|err| match err {
+                    Or::L(err) => into_io_error(err),
+                    Or::R(err) => err,
+                })?;
+                this.dec.char
+            }
+            _ => 0,
+        };
+        *this.seek_op = None;
+        *this.cur = op.eval(*this.cur, len)?;
+        Poll::Ready(Ok(*this.cur))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, ErrorKind, SeekFrom},
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use tokio::io::{AsyncSeek, AsyncSeekExt};
+
+    use super::{Chars, InvalidUtf8, Utf8Policy};
+    use crate::{
+        buf::{self, DataReadBuf},
+        or::Or,
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    /// Fails every read
+    struct Broken;
+
+    impl AsyncDataRead for Broken {
+        type Item = u8;
+        type Err = io::Error;
+
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut impl DataReadBuf<Item = Self::Item>,
+        ) -> Poll<Result<Option<u64>, Self::Err>> {
+            Poll::Ready(Err(ErrorKind::BrokenPipe.into()))
+        }
+    }
+
+    impl AsyncSeek for Broken {
+        fn start_seek(self: Pin<&mut Self>, _: SeekFrom) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn poll_complete(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<u64>> {
+            Poll::Ready(Ok(0))
+        }
+    }
+
+    #[tokio::test]
+    async fn chars() {
+        let data = "aé€😀b".as_bytes().to_vec();
+        let mut source = OverlayOnce::new(data).chars();
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(1, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(5));
+        assert_eq!(buf.filled(), &['é', '€', '😀', 'b']);
+
+        assert_eq!(source.byte_pos(3).await.expect("Read failed!"), Some(6));
+        assert_eq!(source.char_pos(8).await.expect("Read failed!"), Some(3));
+    }
+
+    #[tokio::test]
+    async fn invalid() {
+        let data = vec![b'a', 0xff, b'b'];
+        let mut source = OverlayOnce::new(data.clone()).chars();
+        let mut buf = buf::new::<10, _>();
+        source
+            .read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(buf.filled(), &['a', '\u{fffd}', 'b']);
+
+        let mut source = OverlayOnce::new(data).chars().policy(Utf8Policy::Error);
+        let mut buf = buf::new::<10, _>();
+        let rs = source.read_single_pass(1, &mut buf).await;
+        match rs.map_err(Or::flatten) {
+            Err(Or::L(Or::R(err))) => assert_eq!(err, InvalidUtf8 { pos: 1, byte: 1 }),
+            _ => panic!("Invalid UTF-8 error expected!"),
+        }
+    }
+
+    #[tokio::test]
+    async fn seek_error() {
+        let mut source = Chars::new(Broken);
+        let err = source
+            .seek(SeekFrom::End(0))
+            .await
+            .expect_err("Seek should fail!");
+        assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+    }
+}
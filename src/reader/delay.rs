@@ -1,33 +1,88 @@
-use std::{future::Future, io::SeekFrom, pin::Pin, task::ready, time::Duration};
+use std::{future::Future, io::SeekFrom, ops::Range, pin::Pin, task::ready, time::Duration};
 
 use pin_project::pin_project;
 use tokio::{
     io::AsyncSeek,
-    time::{self, Instant},
+    time::{self, Sleep},
 };
 
-use super::AsyncDataRead;
+use super::{
+    provenance::{LayerPath, Provenance},
+    AsyncDataRead,
+};
+use crate::utils::SplitMix64;
+
+/// How long a read through [`DelayReader`] takes
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Latency {
+    /// Paid by every read
+    pub base: Duration,
+    /// Paid for every item a read asks for
+    pub per_item: Duration,
+    /// Up to this much more, drawn from a generator seeded with `seed`
+    pub jitter: Duration,
+    pub seed: u64,
+    /// Paid by reads that don't continue where the last one ended, the first read included
+    pub seek_penalty: Duration,
+}
+
+impl Latency {
+    /// The same delay for every read
+    pub fn fixed(delay: Duration) -> Self {
+        Self {
+            base: delay,
+            ..Self::default()
+        }
+    }
+
+    fn delay(&self, n: u64, sequential: bool, rng: &mut SplitMix64) -> Duration {
+        let per_item = self
+            .per_item
+            .saturating_mul(n.try_into().unwrap_or(u32::MAX));
+        let penalty = match sequential {
+            true => Duration::ZERO,
+            false => self.seek_penalty,
+        };
+        self.base + per_item + penalty + self.jitter.mul_f64(rng.next_f64())
+    }
+}
 
 #[pin_project]
-/// Induce a delay every time we poll the reader. Mostly for testing - making future not return immediately!
+/// Induce a delay every time we read. Mostly for testing - making future not return immediately!
+///
+/// The delay starts when a read is first polled. With the clock paused by
+/// [`tokio::time::pause`] and a fixed seed, runs are reproducible.
 pub struct DelayReader<R> {
-    duration: Duration,
+    latency: Latency,
+    rng: SplitMix64,
+    /// Armed for the read in progress
     #[pin]
-    delay: time::Sleep,
+    delay: Option<Sleep>,
     #[pin]
     reader: R,
-    seek_op: Option<SeekFrom>,
+    cur: u64,
+    last_end: Option<u64>,
 }
 
 impl<R> DelayReader<R> {
     pub fn new(reader: R, duration: Duration) -> Self {
+        Self::with_latency(reader, Latency::fixed(duration))
+    }
+
+    pub fn with_latency(reader: R, latency: Latency) -> Self {
         Self {
-            duration,
+            latency,
+            rng: SplitMix64::new(latency.seed),
+            delay: None,
             reader,
-            delay: time::sleep(duration),
-            seek_op: None,
+            cur: 0,
+            last_end: None,
         }
     }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
 }
 
 impl<R> AsyncDataRead for DelayReader<R>
@@ -43,17 +98,29 @@ where
         buf: &mut impl crate::buf::DataReadBuf<Item = Self::Item>,
     ) -> std::task::Poll<Result<Option<u64>, Self::Err>> {
         let mut this = self.project();
-        ready!(this.delay.as_mut().poll(cx));
-        this.delay.reset(Instant::now() + *this.duration);
-        this.reader.poll_read(cx, buf)
+        if this.delay.is_none() {
+            let n = (buf.capacity() - buf.filled().len()) as u64;
+            let sequential = *this.last_end == Some(*this.cur);
+            let delay = this.latency.delay(n, sequential, this.rng);
+            this.delay.set(Some(time::sleep(delay)));
+        }
+
+        let delay = this.delay.as_mut().as_pin_mut().expect("Read in progress");
+        ready!(delay.poll(cx));
+        let before = buf.filled().len();
+        let rs = ready!(this.reader.poll_read(cx, buf));
+        this.delay.set(None);
+        *this.cur += (buf.filled().len() - before) as u64;
+        *this.last_end = Some(*this.cur);
+        std::task::Poll::Ready(rs)
     }
 }
 
 impl<R: AsyncSeek> AsyncSeek for DelayReader<R> {
-    fn start_seek(self: Pin<&mut Self>, position: std::io::SeekFrom) -> std::io::Result<()> {
-        let this = self.project();
-        *this.seek_op = Some(position);
-        Ok(())
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        let mut this = self.project();
+        this.delay.set(None);
+        this.reader.start_seek(position)
     }
 
     fn poll_complete(
@@ -61,6 +128,58 @@ impl<R: AsyncSeek> AsyncSeek for DelayReader<R> {
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<std::io::Result<u64>> {
         let this = self.project();
-        this.reader.poll_complete(cx)
+        let pos = ready!(this.reader.poll_complete(cx))?;
+        *this.cur = pos;
+        std::task::Poll::Ready(Ok(pos))
+    }
+}
+
+impl<R: Provenance> Provenance for DelayReader<R> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        self.reader.provenance(range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use super::Latency;
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn latency() {
+        let latency = Latency {
+            base: Duration::from_millis(10),
+            per_item: Duration::from_millis(1),
+            seek_penalty: Duration::from_millis(100),
+            ..Latency::default()
+        };
+        let source = OverlayOnce::new([1, 2, 3, 4, 5, 6]).delay_with(latency);
+        tokio::pin!(source);
+        let start = Instant::now();
+        let mut buf = buf::new::<3, _>();
+        let next = source
+            .read_single_pass(2, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(5));
+        assert_eq!(buf.filled(), &[3, 4, 5]);
+        assert_eq!(start.elapsed(), Duration::from_millis(113));
+
+        // Continues where the last read ended, no penalty
+        let start = Instant::now();
+        let mut buf = buf::new::<3, _>();
+        source
+            .read_single_pass(5, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(buf.filled(), &[6]);
+        assert_eq!(start.elapsed(), Duration::from_millis(13));
     }
 }
@@ -0,0 +1,514 @@
+use std::{
+    io::{self, SeekFrom},
+    ops::Range,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use pin_project::pin_project;
+use tokio::io::AsyncSeek;
+
+use super::{
+    provenance::{stack, under, Layer, LayerPath, Provenance},
+    AsyncDataRead,
+};
+use crate::{
+    buf::DataReadBuf,
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
+
+/// Retry reads that fail on `primary` on `fallback`. Create with [`AsyncDataRead::on_error`]
+#[derive(Debug)]
+#[pin_project]
+pub struct Failover<P: AsyncDataRead, F, Pr> {
+    #[pin]
+    primary: P,
+    #[pin]
+    fallback: F,
+    primary_cur: InnerCursor,
+    fallback_cur: InnerCursor,
+    pred: Pr,
+    sticky_after: Option<usize>,
+    failures: usize,
+    retrying: bool,
+    errors: Vec<(u64, P::Err)>,
+    max_errors: usize,
+    /// Ranges last read from `fallback`, sorted and merged
+    served: Vec<Range<u64>>,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+/// Swallowed errors kept unless [`Failover::keep_errors`] says otherwise
+const MAX_ERRORS: usize = 64;
+
+/// Add `r` to the sorted ranges in `served`, merging it with the ones it touches
+fn mark_served(served: &mut Vec<Range<u64>>, r: Range<u64>) {
+    let i = served.partition_point(|x| x.end < r.start);
+    let j = served.partition_point(|x| x.start <= r.end);
+    if i == j {
+        served.insert(i, r);
+        return;
+    }
+
+    let merged = served[i].start.min(r.start)..served[j - 1].end.max(r.end);
+    served.splice(i..j, [merged]);
+}
+
+/// Remove `r` from the sorted ranges in `served`, splitting the ones it cuts through
+fn unmark_served(served: &mut Vec<Range<u64>>, r: Range<u64>) {
+    let i = served.partition_point(|x| x.end <= r.start);
+    let j = served.partition_point(|x| x.start < r.end);
+    if i == j {
+        return;
+    }
+
+    let (first, last) = (served[i].clone(), served[j - 1].clone());
+    let rest = [first.start..r.start, r.end..last.end];
+    served.splice(i..j, rest.into_iter().filter(|x| x.start < x.end));
+}
+
+impl<P: AsyncDataRead, F> Failover<P, F, fn(&P::Err) -> bool> {
+    /// Every error of `primary` is retried on `fallback`, one read at a time
+    pub fn new(primary: P, fallback: F) -> Self {
+        Self {
+            primary,
+            fallback,
+            primary_cur: InnerCursor::default(),
+            fallback_cur: InnerCursor::default(),
+            pred: |_| true,
+            sticky_after: None,
+            failures: 0,
+            retrying: false,
+            errors: Vec::new(),
+            max_errors: MAX_ERRORS,
+            served: Vec::new(),
+            cur: 0,
+            seek_op: None,
+        }
+    }
+}
+
+impl<P: AsyncDataRead, F, Pr> Failover<P, F, Pr> {
+    /// Only fail over on errors matching `pred`, others are returned as is
+    pub fn when<Q: FnMut(&P::Err) -> bool>(self, pred: Q) -> Failover<P, F, Q> {
+        Failover {
+            primary: self.primary,
+            fallback: self.fallback,
+            primary_cur: self.primary_cur,
+            fallback_cur: self.fallback_cur,
+            pred,
+            sticky_after: self.sticky_after,
+            failures: self.failures,
+            retrying: self.retrying,
+            errors: self.errors,
+            max_errors: self.max_errors,
+            served: self.served,
+            cur: self.cur,
+            seek_op: self.seek_op,
+        }
+    }
+
+    /// Stop going back to `primary` once it has failed `n` times
+    pub fn sticky_after(mut self, n: usize) -> Self {
+        self.sticky_after = Some(n);
+        self
+    }
+
+    /// Keep the last `n` swallowed errors instead of the last 64, older ones are dropped
+    pub fn keep_errors(mut self, n: usize) -> Self {
+        self.max_errors = n;
+        let over = self.errors.len().saturating_sub(n);
+        self.errors.drain(..over);
+        self
+    }
+
+    /// Whether every read goes to `fallback` now
+    pub fn is_sticky(&self) -> bool {
+        self.sticky_after.is_some_and(|n| self.failures >= n)
+    }
+
+    /// The last errors of `primary` that were swallowed, with the position of the failed read
+    pub fn errors(&self) -> &[(u64, P::Err)] {
+        &self.errors
+    }
+
+    /// Drain the swallowed errors, so none are dropped as long as this is called often enough
+    pub fn take_errors(&mut self) -> Vec<(u64, P::Err)> {
+        std::mem::take(&mut self.errors)
+    }
+
+    pub fn into_inner(self) -> (P, F) {
+        (self.primary, self.fallback)
+    }
+}
+
+impl<P, F, Pr> AsyncDataRead for Failover<P, F, Pr>
+where
+    P: AsyncDataRead + AsyncSeek,
+    F: AsyncDataRead<Item = P::Item> + AsyncSeek,
+    Pr: FnMut(&P::Err) -> bool,
+{
+    type Item = P::Item;
+    type Err = Or<Or<P::Err, F::Err>, io::Error>;
+
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let sticky = self.is_sticky();
+        let mut this = self.as_mut().project();
+        let cur = *this.cur;
+        if !sticky && !*this.retrying {
+            ready!(this.primary_cur.poll_seek(this.primary.as_mut(), cx, cur)).map_err(Or::R)?;
+
+            let mut new_buf = buf.take(buf.capacity() - buf.filled().len());
+            match ready!(this.primary.as_mut().poll_read(cx, &mut new_buf)) {
+                Ok(next) => {
+                    let wb = new_buf.filled().len() as u64;
+                    this.primary_cur.advance(wb);
+                    *this.cur += wb;
+                    unmark_served(this.served, cur..*this.cur);
+                    return Poll::Ready(Ok(if wb > 0 { Some(*this.cur) } else { next }));
+                }
+                Err(err) if (this.pred)(&err) => {
+                    // The fallback reads the whole range again
+                    let wb = new_buf.filled().len();
+                    new_buf.shrink(wb);
+                    if *this.max_errors > 0 {
+                        let over = (this.errors.len() + 1).saturating_sub(*this.max_errors);
+                        this.errors.drain(..over);
+                        this.errors.push((cur, err));
+                    }
+                    *this.failures += 1;
+                    *this.retrying = true;
+                    this.primary_cur.reset();
+                }
+                Err(err) => return Poll::Ready(Err(Or::L(Or::L(err)))),
+            }
+        }
+
+        ready!(this.fallback_cur.poll_seek(this.fallback.as_mut(), cx, cur)).map_err(Or::R)?;
+        let mut new_buf = buf.take(buf.capacity() - buf.filled().len());
+        let rs = ready!(this.fallback.as_mut().poll_read(cx, &mut new_buf));
+        // The failed range was handed to the fallback, the next read goes back to primary
+        *this.retrying = false;
+        let next = rs.map_err(|x| Or::L(Or::R(x)))?;
+        let wb = new_buf.filled().len() as u64;
+        this.fallback_cur.advance(wb);
+        *this.cur += wb;
+        if sticky {
+            // Provenance no longer looks at them
+            this.served.clear();
+        } else if wb > 0 {
+            mark_served(this.served, cur..*this.cur);
+        }
+        Poll::Ready(Ok(if wb > 0 { Some(*this.cur) } else { next }))
+    }
+}
+
+impl<P, F, Pr> AsyncSeek for Failover<P, F, Pr>
+where
+    P: AsyncDataRead + AsyncSeek,
+    F: AsyncSeek,
+{
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.project();
+        *this.retrying = false;
+        *this.seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let sticky = self.is_sticky();
+        let this = self.as_mut().project();
+        let Some(op) = *this.seek_op else {
+            return Poll::Ready(Ok(*this.cur));
+        };
+
+        let len = match op {
+            SeekFrom::End(_) if sticky => ready!(this.fallback_cur.poll_len(this.fallback, cx))?,
+            SeekFrom::End(_) => ready!(this.primary_cur.poll_len(this.primary, cx))?,
+            _ => 0,
+        };
+        *this.seek_op = None;
+        *this.cur = op.eval(*this.cur, len)?;
+        Poll::Ready(Ok(*this.cur))
+    }
+}
+
+impl<P, F, Pr> Provenance for Failover<P, F, Pr>
+where
+    P: AsyncDataRead + Provenance,
+    F: Provenance,
+{
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        if self.is_sticky() {
+            return under(Layer::Fallback, self.fallback.provenance(range));
+        }
+
+        let served = self.served.iter().filter_map(|r| {
+            let r = r.start.max(range.start)..r.end.min(range.end);
+            (r.start < r.end).then_some(r)
+        });
+        let top = served
+            .flat_map(|r| under(Layer::Fallback, self.fallback.provenance(r)))
+            .collect();
+        stack(range, top, |hole| {
+            under(Layer::Primary, self.primary.provenance(hole))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::SeekFrom,
+        ops::Range,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use tokio::io::AsyncSeek;
+
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::{
+            overlay_once::OverlayOnce,
+            provenance::{Layer, LayerPath, Provenance},
+            AsyncDataRead,
+        },
+    };
+
+    /// Fails every read
+    struct Broken;
+
+    impl AsyncDataRead for Broken {
+        type Item = u8;
+        type Err = &'static str;
+
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut impl DataReadBuf<Item = Self::Item>,
+        ) -> Poll<Result<Option<u64>, Self::Err>> {
+            Poll::Ready(Err("broken"))
+        }
+    }
+
+    impl AsyncSeek for Broken {
+        fn start_seek(self: Pin<&mut Self>, _: SeekFrom) -> std::io::Result<()> {
+            Ok(())
+        }
+
+        fn poll_complete(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+            Poll::Ready(Ok(0))
+        }
+    }
+
+    impl Provenance for Broken {
+        fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+            vec![(range, Vec::new())]
+        }
+    }
+
+    /// Writes the items before `fail_at`, then fails
+    struct Partial {
+        data: Vec<u8>,
+        cur: u64,
+        fail_at: usize,
+    }
+
+    impl AsyncDataRead for Partial {
+        type Item = u8;
+        type Err = &'static str;
+
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut impl DataReadBuf<Item = Self::Item>,
+        ) -> Poll<Result<Option<u64>, Self::Err>> {
+            let end = self.data.len().min(self.fail_at);
+            let wb = buf.put_slice_guard(self.data.get(self.cur as usize..end).unwrap_or(&[]));
+            self.cur += wb as u64;
+            Poll::Ready(Err("partial"))
+        }
+    }
+
+    impl AsyncSeek for Partial {
+        fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+            if let SeekFrom::Start(x) = position {
+                self.cur = x;
+            }
+            Ok(())
+        }
+
+        fn poll_complete(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+            Poll::Ready(Ok(self.cur))
+        }
+    }
+
+    /// Fails the first `fails` reads, then reads `data`
+    struct Flaky {
+        data: OverlayOnce<u8, Vec<u8>>,
+        fails: usize,
+    }
+
+    impl AsyncDataRead for Flaky {
+        type Item = u8;
+        type Err = &'static str;
+
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut impl DataReadBuf<Item = Self::Item>,
+        ) -> Poll<Result<Option<u64>, Self::Err>> {
+            if self.fails > 0 {
+                self.fails -= 1;
+                return Poll::Ready(Err("flaky"));
+            }
+
+            Pin::new(&mut self.data)
+                .poll_read(cx, buf)
+                .map_err(|_| "flaky")
+        }
+    }
+
+    impl AsyncSeek for Flaky {
+        fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+            Pin::new(&mut self.data).start_seek(position)
+        }
+
+        fn poll_complete(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<std::io::Result<u64>> {
+            Pin::new(&mut self.data).poll_complete(cx)
+        }
+    }
+
+    impl Provenance for Flaky {
+        fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+            self.data.provenance(range)
+        }
+    }
+
+    #[tokio::test]
+    async fn fallback() {
+        let mut source = Broken
+            .on_error(OverlayOnce::new([1u8, 2, 3, 4]))
+            .sticky_after(2);
+        let mut buf = buf::new::<2, _>();
+        let next = source
+            .read_single_pass(1, &mut buf)
+            .await
+            .expect("Read failed!");
+
+        assert_eq!(next, Some(3));
+        assert_eq!(buf.filled(), &[2, 3]);
+        assert_eq!(source.errors(), &[(1, "broken")]);
+        assert!(!source.is_sticky());
+    }
+
+    #[tokio::test]
+    async fn predicate() {
+        let mut source = Broken
+            .on_error(OverlayOnce::new([1u8, 2, 3, 4]))
+            .when(|err| *err != "broken");
+        let mut buf = buf::new::<2, _>();
+        let rs = source.read_single_pass(1, &mut buf).await;
+
+        assert!(rs.is_err());
+        assert!(source.errors().is_empty());
+    }
+
+    #[tokio::test]
+    async fn partial_failure() {
+        let primary = Partial {
+            data: vec![1, 2, 3, 4],
+            cur: 0,
+            fail_at: 2,
+        };
+        let mut source = primary.on_error(OverlayOnce::new([10u8, 20, 30, 40]));
+        let mut buf = buf::new::<4, _>();
+        let next = source
+            .read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+
+        assert_eq!(next, Some(4));
+        assert_eq!(buf.filled(), &[10, 20, 30, 40]);
+    }
+
+    #[tokio::test]
+    async fn served_provenance() {
+        let mut source = Broken.on_error(OverlayOnce::new([1u8, 2, 3, 4, 5, 6]));
+        let mut buf = buf::new::<2, _>();
+        source
+            .read_single_pass(2, &mut buf)
+            .await
+            .expect("Read failed!");
+
+        assert_eq!(
+            source.provenance(0..6),
+            vec![
+                (0..2, vec![Layer::Primary]),
+                (2..4, vec![Layer::Fallback]),
+                (4..6, vec![Layer::Primary]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn keep_errors() {
+        let mut source = Broken
+            .on_error(OverlayOnce::new([1u8, 2, 3, 4]))
+            .keep_errors(2);
+        for pos in 0..3 {
+            let mut buf = buf::new::<1, _>();
+            source
+                .read_single_pass(pos, &mut buf)
+                .await
+                .expect("Read failed!");
+        }
+
+        assert_eq!(source.errors(), &[(1, "broken"), (2, "broken")]);
+        assert_eq!(source.take_errors().len(), 2);
+        assert!(source.errors().is_empty());
+    }
+
+    #[tokio::test]
+    async fn served_trimmed() {
+        let primary = Flaky {
+            data: OverlayOnce::new(vec![1, 2, 3, 4, 5, 6]),
+            fails: 1,
+        };
+        let mut source = primary.on_error(OverlayOnce::new([10u8, 20, 30, 40, 50, 60]));
+        let mut buf = buf::new::<4, _>();
+        source
+            .read_single_pass(1, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(buf.filled(), &[20, 30, 40, 50]);
+
+        // Primary has recovered and serves the middle of the range again
+        let mut buf = buf::new::<2, _>();
+        source
+            .read_single_pass(2, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(buf.filled(), &[3, 4]);
+        assert_eq!(
+            source.provenance(0..6),
+            vec![
+                (0..1, vec![Layer::Primary]),
+                (1..2, vec![Layer::Fallback]),
+                (2..4, vec![Layer::Primary]),
+                (4..5, vec![Layer::Fallback]),
+                (5..6, vec![Layer::Primary]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,368 @@
+use std::{
+    io::{self, SeekFrom},
+    ops::Range,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use pin_project::pin_project;
+use tokio::io::AsyncSeek;
+
+use super::{
+    provenance::{stack, under, Layer, LayerPath, Provenance},
+    AsyncDataRead,
+};
+use crate::{
+    buf::DataReadBuf,
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
+
+/// Report holes as a repeated item. Create with [`AsyncDataRead::fill_holes`]
+#[derive(Debug)]
+#[pin_project]
+pub struct FillHoles<R: AsyncDataRead> {
+    #[pin]
+    reader: R,
+    inner: InnerCursor,
+    value: R::Item,
+    end: Option<u64>,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+impl<R: AsyncDataRead> FillHoles<R> {
+    /// Without `end`, holes after the last data are left as they are
+    pub fn new(reader: R, value: R::Item, end: Option<u64>) -> Self {
+        Self {
+            reader,
+            inner: InnerCursor::default(),
+            value,
+            end,
+            cur: 0,
+            seek_op: None,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R> AsyncDataRead for FillHoles<R>
+where
+    R: AsyncDataRead + AsyncSeek,
+    R::Item: Clone,
+{
+    type Item = R::Item;
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let mut this = self.project();
+        let cur = *this.cur;
+        let end = this.end.unwrap_or(u64::MAX);
+        if cur >= end {
+            return Poll::Ready(Ok(None));
+        }
+
+        ready!(this.inner.poll_seek(this.reader.as_mut(), cx, cur)).map_err(Or::R)?;
+        let unfilled = (buf.capacity() - buf.filled().len()) as u64;
+        let limit = unfilled.min(end - cur);
+        let mut new_buf = buf.take(limit as usize);
+        let next = ready!(this.reader.poll_read(cx, &mut new_buf)).map_err(Or::L)?;
+        let wb = new_buf.filled().len() as u64;
+        drop(new_buf);
+        this.inner.advance(wb);
+        if wb > 0 {
+            *this.cur += wb;
+            return Poll::Ready(Ok(Some(*this.cur).filter(|x| *x < end)));
+        }
+
+        // Fill the whole hole at once, up to where the reader has data again
+        let hole_end = match (next, *this.end) {
+            (Some(x), _) => x.min(end),
+            (None, Some(end)) => end,
+            (None, None) => return Poll::Ready(Ok(None)),
+        };
+        let n = (hole_end - cur).min(limit);
+        buf.put_fill(this.value, n as usize);
+        *this.cur += n;
+        Poll::Ready(Ok(Some(*this.cur).filter(|x| *x < end)))
+    }
+}
+
+impl<R: AsyncDataRead + AsyncSeek> AsyncSeek for FillHoles<R> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        *self.project().seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.project();
+        let Some(op) = *this.seek_op else {
+            return Poll::Ready(Ok(*this.cur));
+        };
+
+        let len = match (op, *this.end) {
+            (SeekFrom::End(_), Some(end)) => end,
+            (SeekFrom::End(_), None) => ready!(this.inner.poll_len(this.reader, cx))?,
+            _ => 0,
+        };
+        *this.seek_op = None;
+        *this.cur = op.eval(*this.cur, len)?;
+        Poll::Ready(Ok(*this.cur))
+    }
+}
+
+/// Whether `reader` has any data at or after `pos`. Looks in growing windows, so a reader with
+/// data close by is not asked about the whole rest of its range
+fn has_data_after<R: Provenance>(reader: &R, pos: u64) -> bool {
+    let (mut start, mut len) = (pos, 1u64);
+    while start < u64::MAX {
+        let end = start.saturating_add(len);
+        if !reader.provenance(start..end).is_empty() {
+            return true;
+        }
+
+        start = end;
+        len = len.saturating_mul(2);
+    }
+
+    false
+}
+
+impl<R: AsyncDataRead + Provenance> Provenance for FillHoles<R> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        let spans = self.reader.provenance(range.clone());
+        let Some(end) = self.end else {
+            // Only the holes before the last data are filled, which may lie past the query
+            let last = match has_data_after(&self.reader, range.end) {
+                true => range.end,
+                false => spans.last().map(|x| x.0.end).unwrap_or(range.start),
+            };
+            return stack(range.start..last, spans, |hole| {
+                vec![(hole, vec![Layer::Fill])]
+            });
+        };
+
+        let range = range.start..range.end.min(end);
+        let spans = spans.into_iter().filter(|x| x.0.start < range.end);
+        let spans = spans
+            .map(|(r, path)| (r.start..r.end.min(range.end), path))
+            .collect();
+        stack(range, spans, |hole| vec![(hole, vec![Layer::Fill])])
+    }
+}
+
+/// Fill the holes of `primary` from `fallback`. Create with [`AsyncDataRead::or_else`]
+#[derive(Debug)]
+#[pin_project]
+pub struct OrElse<P, F> {
+    #[pin]
+    primary: P,
+    #[pin]
+    fallback: F,
+    primary_cur: InnerCursor,
+    fallback_cur: InnerCursor,
+    end: Option<u64>,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+impl<P, F> OrElse<P, F> {
+    /// Nothing at or after `end` is read from either reader
+    pub fn new(primary: P, fallback: F, end: Option<u64>) -> Self {
+        Self {
+            primary,
+            fallback,
+            primary_cur: InnerCursor::default(),
+            fallback_cur: InnerCursor::default(),
+            end,
+            cur: 0,
+            seek_op: None,
+        }
+    }
+
+    pub fn into_inner(self) -> (P, F) {
+        (self.primary, self.fallback)
+    }
+}
+
+impl<P, F> AsyncDataRead for OrElse<P, F>
+where
+    P: AsyncDataRead + AsyncSeek,
+    F: AsyncDataRead<Item = P::Item> + AsyncSeek,
+{
+    type Item = P::Item;
+    type Err = Or<Or<P::Err, F::Err>, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let mut this = self.project();
+        let cur = *this.cur;
+        let end = this.end.unwrap_or(u64::MAX);
+        if cur >= end {
+            return Poll::Ready(Ok(None));
+        }
+
+        let unfilled = (buf.capacity() - buf.filled().len()) as u64;
+        let limit = unfilled.min(end - cur);
+
+        ready!(this.primary_cur.poll_seek(this.primary.as_mut(), cx, cur)).map_err(Or::R)?;
+        let mut new_buf = buf.take(limit as usize);
+        let primary_next = ready!(this.primary.as_mut().poll_read(cx, &mut new_buf))
+            .map_err(|x| Or::L(Or::L(x)))?;
+        let wb = new_buf.filled().len() as u64;
+        drop(new_buf);
+        this.primary_cur.advance(wb);
+        if wb > 0 {
+            *this.cur += wb;
+            return Poll::Ready(Ok(Some(*this.cur)));
+        }
+
+        // Only fill the hole of primary
+        let hole_end = primary_next.unwrap_or(end).min(end);
+        ready!(this.fallback_cur.poll_seek(this.fallback.as_mut(), cx, cur)).map_err(Or::R)?;
+        let mut new_buf = buf.take(limit.min(hole_end - cur) as usize);
+        let fallback_next = ready!(this.fallback.as_mut().poll_read(cx, &mut new_buf))
+            .map_err(|x| Or::L(Or::R(x)))?;
+        let wb = new_buf.filled().len() as u64;
+        this.fallback_cur.advance(wb);
+        if wb > 0 {
+            *this.cur += wb;
+            return Poll::Ready(Ok(Some(*this.cur)));
+        }
+
+        let next = match (primary_next, fallback_next) {
+            (Some(p), Some(f)) => Some(p.min(f)),
+            (p, f) => p.or(f),
+        };
+        Poll::Ready(Ok(next.filter(|x| *x < end)))
+    }
+}
+
+impl<P: AsyncSeek, F: AsyncSeek> AsyncSeek for OrElse<P, F> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        *self.project().seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.project();
+        let Some(op) = *this.seek_op else {
+            return Poll::Ready(Ok(*this.cur));
+        };
+
+        let len = match (op, *this.end) {
+            (SeekFrom::End(_), Some(end)) => end,
+            (SeekFrom::End(_), None) => {
+                let primary = ready!(this.primary_cur.poll_len(this.primary, cx))?;
+                let fallback = ready!(this.fallback_cur.poll_len(this.fallback, cx))?;
+                primary.max(fallback)
+            }
+            _ => 0,
+        };
+        *this.seek_op = None;
+        *this.cur = op.eval(*this.cur, len)?;
+        Poll::Ready(Ok(*this.cur))
+    }
+}
+
+impl<P: Provenance, F: Provenance> Provenance for OrElse<P, F> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        let range = range.start..range.end.min(self.end.unwrap_or(u64::MAX));
+        if range.start >= range.end {
+            return Vec::new();
+        }
+
+        let top = under(Layer::Primary, self.primary.provenance(range.clone()));
+        stack(range, top, |hole| {
+            under(Layer::Fallback, self.fallback.provenance(hole))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::{
+            overlay_once::OverlayOnce,
+            provenance::{Layer, Provenance},
+            AsyncDataRead,
+        },
+    };
+
+    #[tokio::test]
+    async fn fill_holes() {
+        let mut source = OverlayOnce::new([1, 2])
+            .chain_sized(4, OverlayOnce::new([3, 4]))
+            .fill_holes(0, Some(8));
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(2, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(4));
+        assert_eq!(buf.filled(), &[0, 0]);
+
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(6, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, None);
+        assert_eq!(buf.filled(), &[0, 0]);
+    }
+
+    #[tokio::test]
+    async fn fill_holes_provenance() {
+        let mut source = OverlayOnce::new([1, 2])
+            .chain_sized(4, OverlayOnce::new([3, 4]))
+            .fill_holes(0, None);
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(2, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(4));
+        assert_eq!(buf.filled(), &[0, 0]);
+
+        // The gap is filled because data comes after it, even outside the query
+        assert_eq!(source.provenance(2..3), vec![(2..3, vec![Layer::Fill])]);
+        assert_eq!(
+            source.provenance(1..3),
+            vec![(1..2, vec![Layer::Part(0)]), (2..3, vec![Layer::Fill])]
+        );
+        assert_eq!(source.provenance(6..8), vec![]);
+    }
+
+    #[tokio::test]
+    async fn or_else() {
+        let mut source = OverlayOnce::new([1, 2])
+            .chain_sized(4, OverlayOnce::new([3, 4]))
+            .or_else(OverlayOnce::new([10, 20, 30, 40, 50, 60, 70]), None);
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(1, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(2));
+        assert_eq!(buf.filled(), &[2]);
+
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(2, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(4));
+        assert_eq!(buf.filled(), &[30, 40]);
+    }
+}
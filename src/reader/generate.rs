@@ -0,0 +1,316 @@
+use std::{
+    future::Future,
+    io::{self, ErrorKind, SeekFrom},
+    ops::Range,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use pin_project::pin_project;
+use tokio::io::AsyncSeek;
+
+use super::{
+    provenance::{LayerPath, Provenance},
+    AsyncDataRead,
+};
+use crate::{buf::DataReadBuf, utils::SeekFromExt};
+
+fn eval_seek(op: SeekFrom, cur: u64, len: Option<u64>) -> io::Result<u64> {
+    match (op, len) {
+        (SeekFrom::End(_), None) => Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "Seek from the end of an endless reader!",
+        )),
+        (op, len) => op.eval(cur, len.unwrap_or(0)),
+    }
+}
+
+fn generated(range: Range<u64>, len: Option<u64>) -> Vec<(Range<u64>, LayerPath)> {
+    let end = range.end.min(len.unwrap_or(u64::MAX));
+    if range.start >= end {
+        return Vec::new();
+    }
+
+    vec![(range.start..end, Vec::new())]
+}
+
+/// Items computed from their position, endless unless given a length. Create with [`from_fn`]
+#[derive(Debug, Clone)]
+pub struct FromFn<F> {
+    f: F,
+    len: Option<u64>,
+    cur: u64,
+}
+
+/// Reader with item `f(pos)` at every position
+pub fn from_fn<T, F: FnMut(u64) -> T>(f: F) -> FromFn<F> {
+    FromFn {
+        f,
+        len: None,
+        cur: 0,
+    }
+}
+
+impl<F> FromFn<F> {
+    /// End the reader after `len` items
+    pub fn with_len(self, len: u64) -> Self {
+        Self {
+            len: Some(len),
+            ..self
+        }
+    }
+
+    pub fn into_inner(self) -> F {
+        self.f
+    }
+}
+
+impl<T: Clone, F: FnMut(u64) -> T + Unpin> AsyncDataRead for FromFn<F> {
+    type Item = T;
+    type Err = ();
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let this = self.get_mut();
+        let unfilled = (buf.capacity() - buf.filled().len()) as u64;
+        let end = this
+            .len
+            .unwrap_or(u64::MAX)
+            .min(this.cur.saturating_add(unfilled));
+        if this.cur >= end && unfilled > 0 {
+            return Poll::Ready(Ok(None));
+        }
+
+        for pos in this.cur..end {
+            buf.put_slice(&[(this.f)(pos)]);
+        }
+        this.cur = end;
+        Poll::Ready(Ok(Some(end)))
+    }
+}
+
+impl<F: Unpin> AsyncSeek for FromFn<F> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+        this.cur = eval_seek(position, this.cur, this.len)?;
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.cur))
+    }
+}
+
+impl<F> Provenance for FromFn<F> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        generated(range, self.len)
+    }
+}
+
+/// Items produced a chunk at a time by a future. Create with [`from_async_fn`]
+///
+/// Every read asks for the range it can hold. A chunk shorter than asked ends the reader after
+/// it until the next seek, a longer one is cut to the range.
+#[derive(Debug)]
+#[pin_project]
+pub struct FromAsyncFn<F, Fut> {
+    f: F,
+    #[pin]
+    chunk: Option<Fut>,
+    len: Option<u64>,
+    /// Set once a chunk came back short, cleared by a seek
+    end: Option<u64>,
+    cur: u64,
+}
+
+/// Reader with the items of `range` produced by `f(range)`
+pub fn from_async_fn<T, F, Fut>(f: F) -> FromAsyncFn<F, Fut>
+where
+    F: FnMut(Range<u64>) -> Fut,
+    Fut: Future<Output = Vec<T>>,
+{
+    FromAsyncFn {
+        f,
+        chunk: None,
+        len: None,
+        end: None,
+        cur: 0,
+    }
+}
+
+impl<F, Fut> FromAsyncFn<F, Fut> {
+    /// End the reader after `len` items
+    pub fn with_len(self, len: u64) -> Self {
+        Self {
+            len: Some(len),
+            ..self
+        }
+    }
+
+    pub fn into_inner(self) -> F {
+        self.f
+    }
+}
+
+impl<T, F, Fut> AsyncDataRead for FromAsyncFn<F, Fut>
+where
+    T: Clone,
+    F: FnMut(Range<u64>) -> Fut,
+    Fut: Future<Output = Vec<T>>,
+{
+    type Item = T;
+    type Err = ();
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let mut this = self.project();
+        let cur = *this.cur;
+        let unfilled = (buf.capacity() - buf.filled().len()) as u64;
+        if unfilled == 0 {
+            return Poll::Ready(Ok(Some(cur)));
+        }
+
+        let end = this
+            .len
+            .unwrap_or(u64::MAX)
+            .min(this.end.unwrap_or(u64::MAX))
+            .min(cur.saturating_add(unfilled));
+        if cur >= end {
+            return Poll::Ready(Ok(None));
+        }
+
+        if this.chunk.is_none() {
+            let fut = (this.f)(cur..end);
+            this.chunk.set(Some(fut));
+        }
+
+        let fut = this.chunk.as_mut().as_pin_mut().expect("Chunk in progress");
+        let items = ready!(fut.poll(cx));
+        this.chunk.set(None);
+        if (items.len() as u64) < end - cur {
+            *this.end = Some(cur + items.len() as u64);
+        }
+        if items.is_empty() {
+            return Poll::Ready(Ok(None));
+        }
+
+        let wb = buf.put_slice_guard(&items[..items.len().min((end - cur) as usize)]);
+        *this.cur += wb as u64;
+        Poll::Ready(Ok(Some(*this.cur)))
+    }
+}
+
+impl<F, Fut> AsyncSeek for FromAsyncFn<F, Fut> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let mut this = self.project();
+        // A chunk for the old position is no use anymore, nor is where it ended
+        this.chunk.set(None);
+        *this.end = None;
+        *this.cur = eval_seek(position, *this.cur, *this.len)?;
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.cur))
+    }
+}
+
+impl<F, Fut> Provenance for FromAsyncFn<F, Fut> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        let len = match (self.len, self.end) {
+            (Some(x), Some(y)) => Some(x.min(y)),
+            (x, y) => x.or(y),
+        };
+        generated(range, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_async_fn, from_fn};
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::{provenance::Provenance, AsyncDataRead},
+    };
+
+    #[tokio::test]
+    async fn from_fn_bounded() {
+        let mut source = from_fn(|pos| pos * 2).with_len(6);
+        let mut buf = buf::new::<5, _>();
+        let next = source
+            .read_single_pass(3, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(6));
+        assert_eq!(buf.filled(), &[6, 8, 10]);
+
+        let mut buf = buf::new::<5, _>();
+        let next = source
+            .read_single_pass(6, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, None);
+    }
+
+    #[tokio::test]
+    async fn from_fn_near_max() {
+        let mut source = from_fn(|pos| u64::MAX - pos);
+        let mut buf = buf::new::<5, _>();
+        let next = source
+            .read_single_pass(u64::MAX - 2, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(u64::MAX));
+        assert_eq!(buf.filled(), &[2, 1]);
+    }
+
+    #[tokio::test]
+    async fn from_async_fn_chunks() {
+        // Boxed so the reader is Unpin
+        let mut source = from_async_fn(|range: std::ops::Range<u64>| {
+            Box::pin(async move {
+                range
+                    .filter(|x| *x < 7)
+                    .map(|x| x as u8)
+                    .collect::<Vec<_>>()
+            })
+        });
+        let mut buf = buf::new::<4, _>();
+        let next = source
+            .read_single_pass(5, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(7));
+        assert_eq!(buf.filled(), &[5, 6]);
+    }
+
+    #[tokio::test]
+    async fn from_async_fn_pages() {
+        // At most 2 items per call, a short page only ends the reader until the next seek
+        let mut source = from_async_fn(|range: std::ops::Range<u64>| {
+            Box::pin(async move { range.take(2).map(|x| x as u8).collect::<Vec<_>>() })
+        });
+        let mut buf = buf::new::<4, _>();
+        let next = source
+            .read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(2));
+        assert_eq!(buf.filled(), &[0, 1]);
+        assert_eq!(source.provenance(0..4), vec![(0..2, Vec::new())]);
+
+        let mut buf = buf::new::<4, _>();
+        let next = source
+            .read_single_pass(2, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(4));
+        assert_eq!(buf.filled(), &[2, 3]);
+    }
+}
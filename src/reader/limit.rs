@@ -1,51 +1,145 @@
 use std::{
+    io::{self, SeekFrom},
+    ops::Range,
     pin::Pin,
     task::{ready, Context, Poll},
 };
 
-use super::AsyncDataRead;
-use crate::buf::DataReadBuf;
+use super::{
+    provenance::{LayerPath, Provenance},
+    AsyncDataRead,
+};
+use crate::{
+    buf::DataReadBuf,
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
 use pin_project::pin_project;
+use tokio::io::AsyncSeek;
 
 #[derive(Debug, Clone, Copy)]
 #[pin_project]
-pub struct Limit<S>(#[pin] S, usize);
+pub struct Limit<S> {
+    #[pin]
+    reader: S,
+    n: u64,
+    inner: InnerCursor,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
 
 impl<S> Limit<S> {
     pub fn new(data: S, n: usize) -> Self {
-        Self(data, n)
+        Self {
+            reader: data,
+            n: n as u64,
+            inner: InnerCursor::default(),
+            cur: 0,
+            seek_op: None,
+        }
     }
 }
 
-impl<S: AsyncDataRead> AsyncDataRead for Limit<S> {
+impl<S: AsyncDataRead + AsyncSeek> AsyncDataRead for Limit<S> {
     type Item = S::Item;
-    type Err = S::Err;
+    type Err = Or<S::Err, io::Error>;
 
     fn poll_read(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         buf: &mut impl DataReadBuf<Item = Self::Item>,
-        pos: usize,
-    ) -> Poll<Result<Option<usize>, Self::Err>> {
-        let mut new_buf = buf.take(self.1);
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let mut this = self.project();
+        let cur = *this.cur;
+        if cur >= *this.n {
+            return Poll::Ready(Ok(None));
+        }
+
+        ready!(this.inner.poll_seek(this.reader.as_mut(), cx, cur)).map_err(Or::R)?;
+        let mut new_buf = buf.take((*this.n - cur).min(usize::MAX as u64) as usize);
+        let next = ready!(this.reader.poll_read(cx, &mut new_buf)).map_err(Or::L)?;
+        let wb = new_buf.filled().len() as u64;
+        this.inner.advance(wb);
+        *this.cur += wb;
+        Poll::Ready(Ok(next.filter(|x| x < this.n)))
+    }
+}
+
+impl<S: AsyncSeek> AsyncSeek for Limit<S> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        *self.project().seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
         let this = self.project();
-        let poll = ready!(this.0.poll_read(cx, &mut new_buf, pos))?.filter(|x| x < this.1);
-        Poll::Ready(Ok(poll))
+        let Some(op) = *this.seek_op else {
+            return Poll::Ready(Ok(*this.cur));
+        };
+
+        let len = match op {
+            SeekFrom::End(_) => ready!(this.inner.poll_len(this.reader, cx))?.min(*this.n),
+            _ => 0,
+        };
+        *this.seek_op = None;
+        *this.cur = op.eval(*this.cur, len)?;
+        Poll::Ready(Ok(*this.cur))
+    }
+}
+
+impl<S: Provenance> Provenance for Limit<S> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        let end = range.end.min(self.n);
+        if range.start >= end {
+            return Vec::new();
+        }
+
+        self.reader.provenance(range.start..end)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::io::ErrorKind;
+
     use crate::{
         buf::{self, DataReadBuf},
+        or::Or,
         reader::{overlay_once::OverlayOnce, AsyncDataRead},
     };
 
     use super::*;
 
+    /// Reads from the start, but can't seek anywhere
+    #[pin_project]
+    struct Unseekable(#[pin] OverlayOnce<i32, Vec<i32>>);
+
+    impl AsyncDataRead for Unseekable {
+        type Item = i32;
+        type Err = ();
+
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut impl DataReadBuf<Item = Self::Item>,
+        ) -> Poll<Result<Option<u64>, Self::Err>> {
+            self.project().0.poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncSeek for Unseekable {
+        fn start_seek(self: Pin<&mut Self>, _: SeekFrom) -> io::Result<()> {
+            Err(ErrorKind::Unsupported.into())
+        }
+
+        fn poll_complete(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<u64>> {
+            Poll::Ready(Ok(0))
+        }
+    }
+
     #[tokio::test]
     async fn basic() {
-        let source = OverlayOnce::new(0, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        let source = OverlayOnce::new([1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
         let mut ss = Limit::new(source, 3);
         let mut buf = buf::new::<10, _>();
         let next = ss
@@ -56,4 +150,20 @@ mod tests {
         assert_eq!(next, None);
         assert_eq!(buf.filled(), &[1, 2, 3]);
     }
+
+    #[tokio::test]
+    async fn seek_error() {
+        let source = Unseekable(OverlayOnce::new(vec![1, 2, 3, 4]));
+        let mut ss = Limit::new(source, 3);
+        let mut buf = buf::new::<10, _>();
+        for _ in 0..2 {
+            // Reading from where the inner reader happens to be would give the wrong data
+            let rs = ss.read_single_pass(1, &mut buf).await;
+            let Err(Or::R(err)) = rs.map_err(Or::flatten) else {
+                panic!("Seek error expected!");
+            };
+            assert_eq!(err.kind(), ErrorKind::Unsupported);
+            assert!(buf.filled().is_empty());
+        }
+    }
 }
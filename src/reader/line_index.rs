@@ -0,0 +1,529 @@
+use std::{
+    future::Future,
+    io::{self, SeekFrom},
+    ops::Range,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{ready, Context, Poll},
+};
+
+use tokio::io::AsyncSeek;
+
+use super::{limit::Limit, shift::ShiftLeft, AsyncDataRead};
+use crate::{
+    buf::{DataReadBuf, Staging},
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
+
+/// Bytes read per step while scanning
+const CHUNK: usize = 64 * 1024;
+
+/// Read the data at or after `pos`, up to `max` bytes before the next hole. Returns the position
+/// after the bytes handed to `f`, `None` once there is no more data
+async fn read_at<R>(
+    reader: &mut R,
+    stage: &mut Staging<u8>,
+    mut pos: u64,
+    max: usize,
+    f: impl FnOnce(u64, &[u8]),
+) -> Result<Option<u64>, Or<R::Err, io::Error>>
+where
+    R: AsyncDataRead<Item = u8> + AsyncSeek + Unpin,
+{
+    loop {
+        let mut buf = stage.get(max);
+        let next = reader.read_single_pass(pos, &mut buf).await?;
+        let data = buf.filled();
+        if !data.is_empty() {
+            f(pos, data);
+            return Ok(Some(pos + data.len() as u64));
+        }
+
+        match next {
+            Some(x) => pos = x,
+            None => return Ok(None),
+        }
+    }
+}
+
+/// Newlines found from the start of the data up to `scanned`
+#[derive(Debug)]
+struct Scan {
+    /// Bumped by every invalidation, scans started before it are dropped
+    version: u64,
+    stride: u64,
+    /// Start of line `k * stride`
+    checkpoints: Vec<u64>,
+    /// Line starts found so far
+    lines: u64,
+    last_start: u64,
+    scanned: u64,
+    /// End of the data, once the whole reader is scanned
+    len: Option<u64>,
+}
+
+impl Scan {
+    /// Take in `data` read at `pos`, anything between `scanned` and `pos` is a hole
+    fn feed(&mut self, pos: u64, data: &[u8]) {
+        let newlines = data.iter().enumerate().filter(|x| *x.1 == b'\n');
+        for (i, _) in newlines {
+            let start = pos + i as u64 + 1;
+            if self.lines.is_multiple_of(self.stride) {
+                self.checkpoints.push(start);
+            }
+            self.lines += 1;
+            self.last_start = start;
+        }
+        self.scanned = pos + data.len() as u64;
+    }
+
+    /// Take in `data` at `pos` from a scan that started at `from`, what lies between them is a
+    /// hole. Only the part past `scanned` is new
+    fn take_in(&mut self, version: u64, from: u64, pos: u64, data: &[u8]) {
+        if version != self.version || self.len.is_some() || from > self.scanned {
+            return;
+        }
+
+        let end = pos + data.len() as u64;
+        if end > self.scanned {
+            let start = pos.max(self.scanned);
+            self.feed(start, &data[(start - pos) as usize..]);
+        }
+    }
+
+    /// A scan that started at `from` found no more data
+    fn finish(&mut self, version: u64, from: u64) {
+        if version == self.version && from <= self.scanned {
+            self.len.get_or_insert(self.scanned);
+        }
+    }
+
+    /// Take in a read at `pos` that returned `data` and `next`, only the part past `scanned`
+    /// is new
+    fn follow(&mut self, pos: u64, data: &[u8], next: Option<u64>) {
+        if self.len.is_some() || pos > self.scanned {
+            return;
+        }
+
+        let end = pos + data.len() as u64;
+        if end > self.scanned {
+            let from = self.scanned;
+            self.feed(from, &data[(from - pos) as usize..]);
+        } else if data.is_empty() {
+            match next {
+                Some(x) => self.scanned = self.scanned.max(x),
+                None => self.len = Some(self.scanned),
+            }
+        }
+    }
+}
+
+fn lock(scan: &Mutex<Scan>) -> MutexGuard<'_, Scan> {
+    scan.lock().expect("Index poisoned")
+}
+
+/// Scan up to `end` from where `scan` stopped, one chunk at a time. Returns `false` once there is
+/// nothing left to scan
+async fn scan_chunk<R>(
+    reader: &mut R,
+    stage: &mut Staging<u8>,
+    scan: &Mutex<Scan>,
+    end: u64,
+) -> Result<bool, Or<R::Err, io::Error>>
+where
+    R: AsyncDataRead<Item = u8> + AsyncSeek + Unpin,
+{
+    let (from, version) = {
+        let scan = lock(scan);
+        if scan.len.is_some() || scan.scanned >= end {
+            return Ok(false);
+        }
+        (scan.scanned, scan.version)
+    };
+
+    // Another scan may get further meanwhile, the index only takes what's new
+    let max = (end - from).min(CHUNK as u64) as usize;
+    let take = |pos, data: &[u8]| lock(scan).take_in(version, from, pos, data);
+    if read_at(reader, stage, from, max, take).await?.is_none() {
+        lock(scan).finish(version, from);
+    }
+    Ok(true)
+}
+
+/// Newline positions of a byte reader, for reading it by line number. Only the start of every
+/// `stride`-th line is kept, the lines in between are found by reading forward from there.
+///
+/// Holes are skipped over as if they had no newlines in them. The index is built as lookups need
+/// it, by [`Self::scan_some`], and by reads through the index itself: a read that gets past where
+/// scanning stopped indexes the bytes it returns, so reading the data once front to back leaves
+/// it fully indexed. [`Self::scan_in_background`] builds it with a second reader of its own.
+#[derive(Debug)]
+pub struct LineIndex<R> {
+    reader: R,
+    stage: Staging<u8>,
+    scan: Arc<Mutex<Scan>>,
+    inner: InnerCursor,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+impl<R> LineIndex<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_stride(reader, 64)
+    }
+
+    pub fn with_stride(reader: R, stride: u64) -> Self {
+        assert!(stride > 0, "stride must be positive!");
+        Self {
+            reader,
+            stage: Staging::default(),
+            scan: Arc::new(Mutex::new(Scan {
+                version: 0,
+                stride,
+                checkpoints: vec![0],
+                lines: 1,
+                last_start: 0,
+                scanned: 0,
+                len: None,
+            })),
+            inner: InnerCursor::default(),
+            cur: 0,
+            seek_op: None,
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Any change may move every line, so the index is rebuilt on the next lookup. Use
+    /// [`Self::invalidate_from`] and [`Self::get_mut_unchecked`] to keep the lines before an edit
+    pub fn get_mut(&mut self) -> &mut R {
+        self.invalidate_from(0);
+        self.get_mut_unchecked()
+    }
+
+    /// Access the reader without touching the index, call [`Self::invalidate_from`] with the first
+    /// changed position afterwards
+    pub fn get_mut_unchecked(&mut self) -> &mut R {
+        self.inner.reset();
+        &mut self.reader
+    }
+
+    /// Forget every line after `pos`, they are scanned again on the next lookup
+    pub fn invalidate_from(&mut self, pos: u64) {
+        let mut scan = lock(&self.scan);
+        scan.version += 1;
+        // A line start only depends on the bytes before it
+        let keep = scan.checkpoints.partition_point(|x| *x <= pos).max(1);
+        scan.checkpoints.truncate(keep);
+        scan.lines = (keep as u64 - 1) * scan.stride + 1;
+        scan.last_start = scan.checkpoints[keep - 1];
+        scan.scanned = scan.last_start;
+        scan.len = None;
+    }
+
+    /// Whether the whole reader has been scanned
+    pub fn is_complete(&self) -> bool {
+        lock(&self.scan).len.is_some()
+    }
+
+    /// Lines found so far, all of them once [`Self::is_complete`]. A final newline doesn't start
+    /// another line
+    pub fn line_count(&self) -> u64 {
+        let scan = lock(&self.scan);
+        match scan.len {
+            Some(len) if len == scan.last_start => scan.lines - 1,
+            _ => scan.lines,
+        }
+    }
+
+    /// Scan the whole reader in a future of its own, e.g. for [`tokio::spawn`], while lookups use
+    /// what it has found so far. `reader` has to read the same data as the index, edits included.
+    /// Ends once everything is scanned or the index is dropped
+    pub fn scan_in_background<S>(
+        &self,
+        mut reader: S,
+    ) -> impl Future<Output = Result<(), Or<S::Err, io::Error>>> + 'static
+    where
+        S: AsyncDataRead<Item = u8> + AsyncSeek + Unpin + 'static,
+    {
+        let scan = Arc::downgrade(&self.scan);
+        async move {
+            let mut stage = Staging::default();
+            while let Some(scan) = scan.upgrade() {
+                if !scan_chunk(&mut reader, &mut stage, &scan, u64::MAX).await? {
+                    break;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R> LineIndex<R>
+where
+    R: AsyncDataRead<Item = u8> + AsyncSeek + Unpin,
+{
+    /// Scan about `budget` more bytes and return whether the whole reader is indexed. Lookups scan
+    /// as far as they need, calling this between other work builds the index ahead of them
+    pub async fn scan_some(&mut self, budget: u64) -> Result<bool, Or<R::Err, io::Error>> {
+        self.inner.reset();
+        let end = lock(&self.scan).scanned.saturating_add(budget);
+        while scan_chunk(&mut self.reader, &mut self.stage, &self.scan, end).await? {}
+        Ok(self.is_complete())
+    }
+
+    /// Position of the `count`-th newline from `from`, or the end of the data if there are fewer
+    async fn find_newline(&mut self, from: u64, count: u64) -> Result<u64, Or<R::Err, io::Error>> {
+        self.inner.reset();
+        let (mut pos, mut left) = (from, count);
+        loop {
+            let mut found = None;
+            let rs = read_at(&mut self.reader, &mut self.stage, pos, CHUNK, |at, data| {
+                let newlines = data.iter().enumerate().filter(|x| *x.1 == b'\n');
+                for (i, _) in newlines {
+                    left -= 1;
+                    if left == 0 {
+                        found = Some(at + i as u64);
+                        break;
+                    }
+                }
+            })
+            .await?;
+
+            match (found, rs) {
+                (Some(x), _) => return Ok(x),
+                (None, Some(x)) => pos = x,
+                (None, None) => return Ok(pos),
+            }
+        }
+    }
+
+    /// Start of line `n`, `None` if there are fewer lines
+    async fn line_start(&mut self, n: u64) -> Result<Option<u64>, Or<R::Err, io::Error>> {
+        while lock(&self.scan).lines <= n && !self.scan_some(CHUNK as u64).await? {}
+        if n >= self.line_count() {
+            return Ok(None);
+        }
+
+        // A background scan only adds lines, those up to `n` stay put
+        let (from, skip) = {
+            let scan = lock(&self.scan);
+            if n == scan.lines - 1 {
+                return Ok(Some(scan.last_start));
+            }
+            let k = n / scan.stride;
+            (scan.checkpoints[k as usize], n - k * scan.stride)
+        };
+
+        match skip {
+            0 => Ok(Some(from)),
+            skip => Ok(Some(self.find_newline(from, skip).await? + 1)),
+        }
+    }
+
+    /// Line `n` without its newline, `None` if there are fewer lines
+    pub async fn line(
+        &mut self,
+        n: u64,
+    ) -> Result<Option<Limit<ShiftLeft<&mut R>>>, Or<R::Err, io::Error>> {
+        self.lines(n..n + 1).await
+    }
+
+    /// Lines in `range` as one reader, the newlines between them are kept but not the last one.
+    /// `None` if the range goes past the last line
+    pub async fn lines(
+        &mut self,
+        range: Range<u64>,
+    ) -> Result<Option<Limit<ShiftLeft<&mut R>>>, Or<R::Err, io::Error>> {
+        let Some(start) = self.line_start(range.start).await? else {
+            return Ok(None);
+        };
+
+        let end = match range.end.checked_sub(1) {
+            Some(last) if last >= range.start => {
+                let Some(x) = self.line_start(last).await? else {
+                    return Ok(None);
+                };
+                self.find_newline(x, 1).await?
+            }
+            _ => start,
+        };
+
+        let reader = self.get_mut_unchecked();
+        Ok(Some(reader.slice(start as usize, end as usize)))
+    }
+}
+
+impl<R> AsyncDataRead for LineIndex<R>
+where
+    R: AsyncDataRead<Item = u8> + AsyncSeek + Unpin,
+{
+    type Item = u8;
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let this = Pin::into_inner(self);
+        let mut reader = Pin::new(&mut this.reader);
+        ready!(this.inner.poll_seek(reader.as_mut(), cx, this.cur)).map_err(Or::R)?;
+        let before = buf.filled().len();
+        let next = match ready!(reader.poll_read(cx, buf)) {
+            Ok(x) => x,
+            Err(err) => {
+                this.inner.reset();
+                return Poll::Ready(Err(Or::L(err)));
+            }
+        };
+
+        let data = &buf.filled()[before..];
+        lock(&this.scan).follow(this.cur, data, next);
+        this.inner.advance(data.len() as u64);
+        this.cur += data.len() as u64;
+        Poll::Ready(Ok(next))
+    }
+}
+
+impl<R: AsyncSeek + Unpin> AsyncSeek for LineIndex<R> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        Pin::into_inner(self).seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = Pin::into_inner(self);
+        let Some(op) = this.seek_op else {
+            return Poll::Ready(Ok(this.cur));
+        };
+
+        let len = match op {
+            SeekFrom::End(_) => ready!(this.inner.poll_len(Pin::new(&mut this.reader), cx))?,
+            _ => 0,
+        };
+        this.seek_op = None;
+        this.cur = op.eval(this.cur, len)?;
+        Poll::Ready(Ok(this.cur))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineIndex;
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::{overlay_once::OverlayOnce, piece_table::PieceTable, AsyncDataRead},
+    };
+
+    #[tokio::test]
+    async fn line() {
+        let data = b"ab\ncd\n\nef".to_vec();
+        let mut index = LineIndex::with_stride(OverlayOnce::new(data), 2);
+        assert!(index.scan_some(u64::MAX).await.expect("Scan failed!"));
+        assert_eq!(index.line_count(), 4);
+
+        let mut line = index
+            .line(1)
+            .await
+            .expect("Scan failed!")
+            .expect("Line expected!");
+        let mut buf = buf::new::<10, _>();
+        line.read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(buf.filled(), b"cd");
+    }
+
+    #[tokio::test]
+    async fn scan_while_reading() {
+        let data = b"ab\ncd\n\nef".to_vec();
+        let mut index = LineIndex::with_stride(OverlayOnce::new(data), 2);
+        let mut buf = buf::new::<4, _>();
+        index
+            .read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(buf.filled(), b"ab\nc");
+        assert!(!index.is_complete());
+        assert_eq!(index.line_count(), 2);
+
+        // Overlaps what was scanned, only the rest is taken in
+        let mut buf = buf::new::<10, _>();
+        let next = index
+            .read_single_pass(2, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(9));
+        assert_eq!(buf.filled(), b"\ncd\n\nef");
+        let mut buf = buf::new::<10, _>();
+        let next = index
+            .read_single_pass(9, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, None);
+        assert!(index.is_complete());
+        assert_eq!(index.line_count(), 4);
+
+        let mut line = index
+            .line(3)
+            .await
+            .expect("Scan failed!")
+            .expect("Line expected!");
+        let mut buf = buf::new::<10, _>();
+        line.read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(buf.filled(), b"ef");
+    }
+
+    #[tokio::test]
+    async fn background_scan() {
+        let data = b"ab\ncd\n\nef".to_vec();
+        let mut index = LineIndex::with_stride(OverlayOnce::new(data.clone()), 2);
+        let scan = tokio::spawn(index.scan_in_background(OverlayOnce::new(data)));
+        scan.await.expect("Scan panicked!").expect("Scan failed!");
+        assert!(index.is_complete());
+        assert_eq!(index.line_count(), 4);
+
+        let mut line = index
+            .line(3)
+            .await
+            .expect("Scan failed!")
+            .expect("Line expected!");
+        let mut buf = buf::new::<10, _>();
+        line.read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(buf.filled(), b"ef");
+    }
+
+    #[tokio::test]
+    async fn rebuild() {
+        let table = PieceTable::new(OverlayOnce::new(b"ab\ncd\n".to_vec()), 6);
+        let mut index = LineIndex::with_stride(table, 2);
+        assert!(index.scan_some(u64::MAX).await.expect("Scan failed!"));
+        assert_eq!(index.line_count(), 2);
+
+        index.get_mut().insert(0, b"x\n");
+        let mut lines = index
+            .lines(1..3)
+            .await
+            .expect("Scan failed!")
+            .expect("Lines expected!");
+        let mut buf = buf::new::<10, _>();
+        lines
+            .read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(buf.filled(), b"ab\ncd");
+        assert_eq!(index.line_count(), 3);
+    }
+}
@@ -0,0 +1,445 @@
+use std::{
+    io::{self, SeekFrom},
+    ops::Range,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use pin_project::pin_project;
+use tokio::io::AsyncSeek;
+
+use super::{
+    provenance::{LayerPath, Provenance},
+    AsyncDataRead,
+};
+use crate::{
+    buf::{DataReadBuf, Staging},
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
+
+/// Convert every item. Create with [`AsyncDataRead::map`]
+#[derive(Debug)]
+#[pin_project]
+pub struct Map<R: AsyncDataRead, F> {
+    #[pin]
+    reader: R,
+    f: F,
+    stage: Staging<R::Item>,
+}
+
+impl<R: AsyncDataRead, F> Map<R, F> {
+    pub fn new(reader: R, f: F) -> Self {
+        Self {
+            reader,
+            f,
+            stage: Staging::default(),
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R, F, U> AsyncDataRead for Map<R, F>
+where
+    R: AsyncDataRead,
+    F: FnMut(R::Item) -> U,
+{
+    type Item = U;
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let this = self.project();
+        let mut stage = this.stage.get(buf.capacity() - buf.filled().len());
+        let next = ready!(this.reader.poll_read(cx, &mut stage)).map_err(Or::L)?;
+        for x in stage.drain() {
+            buf.push((this.f)(x));
+        }
+
+        Poll::Ready(Ok(next))
+    }
+}
+
+impl<R: AsyncDataRead + AsyncSeek, F> AsyncSeek for Map<R, F> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        self.project().reader.start_seek(position)
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        self.project().reader.poll_complete(cx)
+    }
+}
+
+impl<R: AsyncDataRead + Provenance, F> Provenance for Map<R, F> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        self.reader.provenance(range)
+    }
+}
+
+/// A [`TryMap`] conversion failed at `pos`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapError<E> {
+    pub pos: u64,
+    pub err: E,
+}
+
+/// Where a [`TryMap`] or [`FilterMap`] is and where it left the reader it converts
+#[derive(Debug)]
+struct Cursor<T> {
+    cur: u64,
+    inner: InnerCursor,
+    seek_op: Option<SeekFrom>,
+    stage: Staging<T>,
+}
+
+impl<T> Cursor<T> {
+    fn new() -> Self {
+        Self {
+            cur: 0,
+            inner: InnerCursor::default(),
+            seek_op: None,
+            stage: Staging::default(),
+        }
+    }
+
+    fn poll_complete<R: AsyncSeek>(
+        &mut self,
+        reader: Pin<&mut R>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<u64>> {
+        let Some(op) = self.seek_op else {
+            return Poll::Ready(Ok(self.cur));
+        };
+
+        let len = match op {
+            SeekFrom::End(_) => ready!(self.inner.poll_len(reader, cx))?,
+            _ => 0,
+        };
+        self.seek_op = None;
+        self.cur = op.eval(self.cur, len)?;
+        Poll::Ready(Ok(self.cur))
+    }
+}
+
+/// Convert every item, conversion errors carry their position. Create with
+/// [`AsyncDataRead::try_map`]
+#[derive(Debug)]
+#[pin_project]
+pub struct TryMap<R: AsyncDataRead, F> {
+    #[pin]
+    reader: R,
+    f: F,
+    cursor: Cursor<R::Item>,
+}
+
+impl<R: AsyncDataRead, F> TryMap<R, F> {
+    pub fn new(reader: R, f: F) -> Self {
+        Self {
+            reader,
+            f,
+            cursor: Cursor::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R, F, U, E> AsyncDataRead for TryMap<R, F>
+where
+    R: AsyncDataRead + AsyncSeek,
+    F: FnMut(R::Item) -> Result<U, E>,
+{
+    type Item = U;
+    type Err = Or<Or<R::Err, MapError<E>>, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let mut this = self.project();
+        let cursor = this.cursor;
+        ready!(cursor.inner.poll_seek(this.reader.as_mut(), cx, cursor.cur)).map_err(Or::R)?;
+
+        let mut stage = cursor.stage.get(buf.capacity() - buf.filled().len());
+        let next = ready!(this.reader.poll_read(cx, &mut stage)).map_err(|x| Or::L(Or::L(x)))?;
+        cursor.inner.advance(stage.filled().len() as u64);
+        for (written, x) in stage.drain().enumerate() {
+            match (this.f)(x) {
+                Ok(x) => buf.push(x),
+                Err(err) if written == 0 => {
+                    let pos = cursor.cur;
+                    return Poll::Ready(Err(Or::L(Or::R(MapError { pos, err }))));
+                }
+                // Hand out what converted so far, the next read starts at the failed item
+                Err(_) => return Poll::Ready(Ok(Some(cursor.cur))),
+            }
+            cursor.cur += 1;
+        }
+
+        Poll::Ready(Ok(next))
+    }
+}
+
+impl<R: AsyncDataRead + AsyncSeek, F> AsyncSeek for TryMap<R, F> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        self.project().cursor.seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.project();
+        this.cursor.poll_complete(this.reader, cx)
+    }
+}
+
+impl<R: AsyncDataRead + Provenance, F> Provenance for TryMap<R, F> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        self.reader.provenance(range)
+    }
+}
+
+/// Convert every item, items converted to `None` become holes. Create with
+/// [`AsyncDataRead::filter_map`] or [`AsyncDataRead::mask`]
+#[derive(Debug)]
+#[pin_project]
+pub struct FilterMap<R: AsyncDataRead, F, U> {
+    #[pin]
+    reader: R,
+    f: F,
+    cursor: Cursor<R::Item>,
+    /// First item after a hole, converted by the read that found the hole
+    peeked: Option<(u64, U)>,
+}
+
+impl<R: AsyncDataRead, F, U> FilterMap<R, F, U> {
+    pub fn new(reader: R, f: F) -> Self {
+        Self {
+            reader,
+            f,
+            cursor: Cursor::new(),
+            peeked: None,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R, F, U> AsyncDataRead for FilterMap<R, F, U>
+where
+    R: AsyncDataRead + AsyncSeek,
+    F: FnMut(R::Item) -> Option<U>,
+{
+    type Item = U;
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let mut this = self.project();
+        let cursor = this.cursor;
+        let mut unfilled = buf.capacity() - buf.filled().len();
+        let mut written = 0;
+        match this.peeked.take() {
+            Some((pos, x)) if pos == cursor.cur && unfilled > 0 => {
+                buf.push(x);
+                cursor.cur += 1;
+                unfilled -= 1;
+                written += 1;
+            }
+            Some(peeked) if peeked.0 == cursor.cur => *this.peeked = Some(peeked),
+            _ => {}
+        }
+
+        // Hand out the peeked item rather than wait for more
+        let short = Poll::Ready(Ok(Some(cursor.cur)));
+        if written > 0 && unfilled == 0 {
+            return short;
+        }
+
+        match cursor.inner.poll_seek(this.reader.as_mut(), cx, cursor.cur) {
+            Poll::Ready(Ok(())) => {}
+            // The next read runs into the error again
+            Poll::Ready(Err(_)) | Poll::Pending if written > 0 => return short,
+            Poll::Ready(Err(err)) => return Poll::Ready(Err(Or::R(err))),
+            Poll::Pending => return Poll::Pending,
+        }
+
+        let mut stage = cursor.stage.get(unfilled);
+        let next = match this.reader.poll_read(cx, &mut stage) {
+            Poll::Ready(next) => next.map_err(Or::L)?,
+            Poll::Pending if written > 0 => return short,
+            Poll::Pending => return Poll::Pending,
+        };
+        let staged = stage.filled().len() as u64;
+        cursor.inner.advance(staged);
+        if staged == 0 {
+            return if written > 0 {
+                short
+            } else {
+                Poll::Ready(Ok(next))
+            };
+        }
+
+        let start = cursor.cur;
+        for (i, x) in stage.drain().enumerate() {
+            match (this.f)(x) {
+                // Skipped a hole, data begins here
+                Some(x) if written == 0 && i > 0 => {
+                    let pos = start + i as u64;
+                    *this.peeked = Some((pos, x));
+                    return Poll::Ready(Ok(Some(pos)));
+                }
+                Some(x) => {
+                    buf.push(x);
+                    written += 1;
+                    cursor.cur += 1;
+                }
+                None if written == 0 => {}
+                None => return Poll::Ready(Ok(Some(cursor.cur))),
+            }
+        }
+
+        if written > 0 {
+            return Poll::Ready(Ok(Some(cursor.cur)));
+        }
+
+        // Every staged item was a hole, data can only come back after them
+        Poll::Ready(Ok(Some(start + staged)))
+    }
+}
+
+impl<R: AsyncDataRead + AsyncSeek, F, U> AsyncSeek for FilterMap<R, F, U> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        self.project().cursor.seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.project();
+        this.cursor.poll_complete(this.reader, cx)
+    }
+}
+
+// Without running the conversion the items it turns into holes are unknown, so every range of the
+// reader is reported
+impl<R: AsyncDataRead + Provenance, F, U> Provenance for FilterMap<R, F, U> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        self.reader.provenance(range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use crate::{
+        buf::{self, DataReadBuf},
+        or::Or,
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    #[tokio::test]
+    async fn map() {
+        let mut source = OverlayOnce::new([1u8, 2, 3, 4]).map(|x| x as char);
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(1, &mut buf)
+            .await
+            .expect("Read failed!");
+
+        assert_eq!(next, Some(4));
+        assert_eq!(buf.filled(), &['\u{2}', '\u{3}', '\u{4}']);
+    }
+
+    #[tokio::test]
+    async fn moved_items() {
+        /// Can only be moved between the conversions
+        struct Token(u8);
+
+        let mut source = OverlayOnce::new([1u8, 0, 3])
+            .map(Token)
+            .try_map(|x| Ok::<_, ()>(Token(x.0 * 2)))
+            .filter_map(|x| (x.0 > 0).then_some(x.0));
+        let mut buf = buf::new::<10, _>();
+        let next = source.read(0, &mut buf).await.expect("Read failed!");
+        assert_eq!(next, Some(2));
+        assert_eq!(buf.filled(), &[2]);
+
+        let mut buf = buf::new::<10, _>();
+        let next = source.read(2, &mut buf).await.expect("Read failed!");
+        assert_eq!(next, None);
+        assert_eq!(buf.filled(), &[6]);
+    }
+
+    #[tokio::test]
+    async fn try_map() {
+        let mut source = OverlayOnce::new([1, 2, 0, 4]).try_map(|x| 12u32.checked_div(x).ok_or(x));
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(2));
+        assert_eq!(buf.filled(), &[12, 6]);
+
+        let mut buf = buf::new::<10, _>();
+        let rs = source.read_single_pass(2, &mut buf).await;
+        let Err(Or::L(Or::R(err))) = rs.map_err(Or::flatten) else {
+            panic!("Conversion error expected!");
+        };
+        assert_eq!(err.pos, 2);
+    }
+
+    #[tokio::test]
+    async fn mask() {
+        let mut source = OverlayOnce::new([0, 0, 1, 2, 0, 3]).mask(|x| *x != 0);
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(2));
+        assert_eq!(buf.filled(), &[]);
+
+        let next = source
+            .read_single_pass(2, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(4));
+        assert_eq!(buf.filled(), &[1, 2]);
+    }
+
+    #[tokio::test]
+    async fn filter_map_once() {
+        let calls = Cell::new(0);
+        let mut source = OverlayOnce::new([0, 0, 1, 2]).filter_map(|x| {
+            calls.set(calls.get() + 1);
+            (x != 0).then_some(x * 10)
+        });
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(2));
+        assert_eq!(calls.get(), 3);
+
+        let mut buf = buf::new::<10, _>();
+        source.read(2, &mut buf).await.expect("Read failed!");
+        assert_eq!(buf.filled(), &[10, 20]);
+        assert_eq!(calls.get(), 4);
+    }
+}
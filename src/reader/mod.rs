@@ -1,40 +1,85 @@
+pub mod bits;
+pub mod block;
+pub mod cast;
+pub mod chain;
+pub mod chars;
 pub mod delay;
+pub mod failover;
+pub mod fill;
+pub mod generate;
 pub mod limit;
+pub mod line_index;
+pub mod map;
 pub mod overlay;
 pub mod overlay_list;
 pub mod overlay_once;
+pub mod parity;
+pub mod piece_table;
+pub mod provenance;
+pub mod rate_limit;
+pub mod records;
+pub mod repeat;
+pub mod retry;
+pub mod reverse;
+pub mod rle;
 pub mod shift;
+pub mod stride;
+pub mod stripe;
+pub mod timeout;
+pub mod vote;
+pub mod zip;
 
 use std::{
-    future::{poll_fn, Future, PollFn},
-    mem,
+    future::Future,
+    io, mem,
     ops::DerefMut,
     pin::Pin,
     task::{Context, Poll},
     time::Duration,
 };
 
-use crate::buf::DataReadBuf;
-use delay::DelayReader;
+use tokio::{io::AsyncSeek, time::Instant};
+
+use crate::{buf::DataReadBuf, or::Or, utils::InnerCursor};
+use bits::{BitOrder, Bits};
+use block::{BlockTransform, Blocks};
+use cast::{Cast, Endian, Numeric};
+use chain::Chain;
+use chars::Chars;
+use delay::{DelayReader, Latency};
+use failover::Failover;
+use fill::{FillHoles, OrElse};
 use limit::Limit;
+use map::{FilterMap, Map, TryMap};
 use overlay::OverlaySource;
 use overlay_once::OverlayOnce;
+use piece_table::PieceTable;
+use provenance::Annotated;
+use rate_limit::{RateLimit, TokenBucket};
+use records::{DynRecords, Records};
+use repeat::Repeat;
+use retry::{Retry, RetryPolicy};
+use reverse::Reverse;
 use shift::{ShiftLeft, ShiftRight};
+use stride::StepBy;
+use stripe::{StripeLayout, StripeMember};
+use timeout::Timeout;
+use zip::Zip;
 
 async fn read_to_hole0<R, B>(
     reader: &mut R,
     buf: &mut B,
-    pos: usize,
-) -> Result<Option<usize>, R::Err>
+    pos: u64,
+) -> Result<Option<u64>, Or<R::Err, io::Error>>
 where
-    R: AsyncDataRead + Unpin,
+    R: AsyncDataRead + AsyncSeek + Unpin,
     B: DataReadBuf<Item = R::Item>,
 {
     let mut written = 0;
     loop {
         let mut clean_buf = buf.take(buf.capacity() - buf.filled().len());
         let next = reader
-            .read_single_pass(pos + written, &mut clean_buf)
+            .read_single_pass(pos + written as u64, &mut clean_buf)
             .await?;
         let wb = clean_buf.filled().len();
         written += wb;
@@ -55,28 +100,31 @@ pub trait AsyncDataRead {
         buf: &mut impl DataReadBuf<Item = Self::Item>,
     ) -> Poll<Result<Option<u64>, Self::Err>>;
 
+    /// Seek to `pos` and read once
     fn read_single_pass<'b, B: DataReadBuf<Item = Self::Item>>(
         &mut self,
-        pos: usize,
+        pos: u64,
         buf: &'b mut B,
     ) -> ReadFut<'_, 'b, Self, B>
     where
-        Self: Sized + Unpin,
+        Self: Sized + AsyncSeek + Unpin,
     {
         ReadFut::Pending(ReadFutData {
             reader: self,
             buf,
             pos,
+            cursor: InnerCursor::default(),
         })
     }
 
-    fn read<'b, B: DataReadBuf<Item = Self::Item>>(
+    /// Seek to `pos` and read until the buffer is full or the first hole
+    fn read<B: DataReadBuf<Item = Self::Item>>(
         &mut self,
-        pos: usize,
-        buf: &'b mut B,
-    ) -> impl Future<Output = Result<Option<usize>, Self::Err>>
+        pos: u64,
+        buf: &mut B,
+    ) -> impl Future<Output = Result<Option<u64>, Or<Self::Err, io::Error>>>
     where
-        Self: Sized + Unpin,
+        Self: Sized + AsyncSeek + Unpin,
     {
         read_to_hole0(self, buf, pos)
     }
@@ -91,16 +139,16 @@ pub trait AsyncDataRead {
         OverlaySource::new(self, other)
     }
 
-    fn overlay_once<'a>(
+    fn overlay_once(
         self,
         pos: usize,
-        data: &'a [Self::Item],
-    ) -> OverlaySource<Self::Item, Self, OverlayOnce<Self::Item, &'a [Self::Item]>>
+        data: &[Self::Item],
+    ) -> OverlaySource<Self::Item, Self, ShiftRight<OverlayOnce<Self::Item, &[Self::Item]>>>
     where
         Self: Sized,
         Self::Item: Clone,
     {
-        self.overlay(OverlayOnce::new(pos, data))
+        self.overlay(OverlayOnce::new(data).shift_right(pos))
     }
 
     fn shift_left(self, n: usize) -> ShiftLeft<Self>
@@ -129,7 +177,182 @@ pub trait AsyncDataRead {
         Self: Sized,
     {
         let len = end - start;
-        self.shift_left(start).limit(len)
+        Limit::new(ShiftLeft::new(self, start), len)
+    }
+
+    fn map<U, F: FnMut(Self::Item) -> U>(self, f: F) -> Map<Self, F>
+    where
+        Self: Sized,
+    {
+        Map::new(self, f)
+    }
+
+    fn try_map<U, E, F: FnMut(Self::Item) -> Result<U, E>>(self, f: F) -> TryMap<Self, F>
+    where
+        Self: Sized,
+    {
+        TryMap::new(self, f)
+    }
+
+    fn filter_map<U, F: FnMut(Self::Item) -> Option<U>>(self, f: F) -> FilterMap<Self, F, U>
+    where
+        Self: Sized,
+    {
+        FilterMap::new(self, f)
+    }
+
+    /// Turn items that fail `pred` into holes
+    fn mask<P: FnMut(&Self::Item) -> bool>(
+        self,
+        mut pred: P,
+    ) -> FilterMap<Self, impl FnMut(Self::Item) -> Option<Self::Item>, Self::Item>
+    where
+        Self: Sized,
+    {
+        FilterMap::new(self, move |x| pred(&x).then_some(x))
+    }
+
+    /// Report holes before `end` as `value`, without `end` only holes between data are filled
+    fn fill_holes(self, value: Self::Item, end: Option<u64>) -> FillHoles<Self>
+    where
+        Self: Sized,
+    {
+        FillHoles::new(self, value, end)
+    }
+
+    /// Fill holes before `end` from `fallback`
+    fn or_else<F: AsyncDataRead<Item = Self::Item>>(
+        self,
+        fallback: F,
+        end: Option<u64>,
+    ) -> OrElse<Self, F>
+    where
+        Self: Sized,
+    {
+        OrElse::new(self, fallback, end)
+    }
+
+    /// Retry failed reads on `fallback`, see [`Failover`] for the policy
+    fn on_error<F: AsyncDataRead<Item = Self::Item>>(
+        self,
+        fallback: F,
+    ) -> Failover<Self, F, fn(&Self::Err) -> bool>
+    where
+        Self: Sized,
+    {
+        Failover::new(self, fallback)
+    }
+
+    fn chain<O: AsyncDataRead<Item = Self::Item>>(self, other: O) -> Chain<Self, O>
+    where
+        Self: Sized,
+    {
+        Chain::new(self, other, None)
+    }
+
+    fn chain_sized<O: AsyncDataRead<Item = Self::Item>>(self, len: u64, other: O) -> Chain<Self, O>
+    where
+        Self: Sized,
+    {
+        Chain::new(self, other, Some(len))
+    }
+
+    /// Pair up items with `other`, positions where either one has a hole are holes
+    fn zip<O: AsyncDataRead>(self, other: O) -> Zip<Self, O>
+    where
+        Self: Sized,
+    {
+        Zip::new(self, other)
+    }
+
+    /// Read bytes bit by bit, `order` picks which bit of a byte comes first
+    fn bits(self, order: BitOrder) -> Bits<Self>
+    where
+        Self: Sized + AsyncDataRead<Item = u8>,
+    {
+        Bits::new(self, order)
+    }
+
+    /// Read through a transform that works on whole blocks
+    fn block_transform<X: BlockTransform<Item = Self::Item>>(self, transform: X) -> Blocks<Self, X>
+    where
+        Self: Sized,
+    {
+        Blocks::new(self, transform)
+    }
+
+    /// Read bytes as an array of `T` in byte order `E`
+    fn cast<T: Numeric, E: Endian>(self) -> Cast<Self, T, E>
+    where
+        Self: Sized + AsyncDataRead<Item = u8>,
+    {
+        Cast::new(self)
+    }
+
+    /// Decode bytes as UTF-8 chars
+    fn chars(self) -> Chars<Self>
+    where
+        Self: Sized + AsyncDataRead<Item = u8>,
+    {
+        Chars::new(self)
+    }
+
+    /// Group every `N` items into one record item
+    fn records<const N: usize>(self) -> Records<Self, N>
+    where
+        Self: Sized,
+        Self::Item: Clone,
+    {
+        Records::new(self)
+    }
+
+    /// Group every `size` items into one record item
+    fn records_of(self, size: u64) -> DynRecords<Self>
+    where
+        Self: Sized,
+        Self::Item: Clone,
+    {
+        DynRecords::new(self, size)
+    }
+
+    /// Repeat the first `period` items forever, see [`Repeat::times`] to stop early
+    fn repeat(self, period: u64) -> Repeat<Self>
+    where
+        Self: Sized,
+    {
+        Repeat::new(self, Some(period))
+    }
+
+    /// Repeat the whole reader forever, see [`Repeat::times`] to stop early
+    fn cycle(self) -> Repeat<Self>
+    where
+        Self: Sized,
+    {
+        Repeat::new(self, None)
+    }
+
+    /// Read back to front, the length is found by seeking to the end
+    fn reverse(self) -> Reverse<Self>
+    where
+        Self: Sized,
+    {
+        Reverse::new(self, None)
+    }
+
+    /// Every `step`-th item starting at `phase`, see [`stride::interleave`] to merge lanes back
+    fn step_by(self, step: u64, phase: u64) -> StepBy<Self>
+    where
+        Self: Sized,
+    {
+        StepBy::new(self, step, phase)
+    }
+
+    /// Treat this reader as the logical side of a stripe set and read out one of its members
+    fn stripe_member(self, layout: StripeLayout, member: usize) -> StripeMember<Self>
+    where
+        Self: Sized,
+    {
+        StripeMember::new(self, layout, member)
     }
 
     fn delay(self, delay: Duration) -> DelayReader<Self>
@@ -138,6 +361,68 @@ pub trait AsyncDataRead {
     {
         DelayReader::new(self, delay)
     }
+
+    /// Delay reads as `latency` says, see [`Latency`]
+    fn delay_with(self, latency: Latency) -> DelayReader<Self>
+    where
+        Self: Sized,
+    {
+        DelayReader::with_latency(self, latency)
+    }
+
+    /// Fail any read that takes longer than `per_read`
+    fn timeout(self, per_read: Duration) -> Timeout<Self>
+    where
+        Self: Sized,
+    {
+        Timeout::new(self, Some(per_read), None)
+    }
+
+    /// Fail any read still running at `deadline`
+    fn deadline(self, deadline: Instant) -> Timeout<Self>
+    where
+        Self: Sized,
+    {
+        Timeout::new(self, None, Some(deadline))
+    }
+
+    /// Read at most `items_per_sec` items per second, after up to `burst` items at once
+    fn rate_limit(self, items_per_sec: f64, burst: u64) -> RateLimit<Self>
+    where
+        Self: Sized,
+    {
+        RateLimit::new(self, TokenBucket::new(items_per_sec, burst))
+    }
+
+    /// Read on the budget of `bucket`, shared with every reader using a clone of it
+    fn rate_limit_with(self, bucket: TokenBucket) -> RateLimit<Self>
+    where
+        Self: Sized,
+    {
+        RateLimit::new(self, bucket)
+    }
+
+    /// Try failed reads again as `policy` says, see [`Retry`]
+    fn retry(self, policy: RetryPolicy) -> Retry<Self, fn(&Self::Err) -> bool>
+    where
+        Self: Sized,
+    {
+        Retry::new(self, policy)
+    }
+
+    fn piece_table(self, len: u64) -> PieceTable<Self>
+    where
+        Self: Sized,
+    {
+        PieceTable::new(self, len)
+    }
+
+    fn annotated(self) -> Annotated<Self>
+    where
+        Self: Sized,
+    {
+        Annotated::new(self)
+    }
 }
 
 impl<S: AsyncDataRead, Ptr> AsyncDataRead for Pin<Ptr>
@@ -151,10 +436,22 @@ where
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         buf: &mut impl DataReadBuf<Item = Self::Item>,
-        pos: usize,
-    ) -> Poll<Result<Option<usize>, Self::Err>> {
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
         let pin_deref = Pin::as_deref_mut(self);
-        S::poll_read(pin_deref, cx, buf, pos)
+        S::poll_read(pin_deref, cx, buf)
+    }
+}
+
+impl<S: AsyncDataRead + Unpin + ?Sized> AsyncDataRead for &mut S {
+    type Item = S::Item;
+    type Err = S::Err;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        Pin::new(&mut **self.get_mut()).poll_read(cx, buf)
     }
 }
 
@@ -168,30 +465,39 @@ pub enum ReadFut<'s, 'b, R, B> {
 pub struct ReadFutData<'s, 'b, R, B> {
     reader: &'s mut R,
     buf: &'b mut B,
-    pos: usize,
+    pos: u64,
+    cursor: InnerCursor,
 }
 
-impl<'s, 'b, 'i, R, B, T> Future for ReadFut<'s, 'b, R, B>
+impl<'s, 'b, R, B, T> Future for ReadFut<'s, 'b, R, B>
 where
-    R: AsyncDataRead<Item = T> + Unpin,
+    R: AsyncDataRead<Item = T> + AsyncSeek + Unpin,
     B: DataReadBuf<Item = T>,
 {
-    type Output = Result<Option<usize>, R::Err>;
+    type Output = Result<Option<u64>, Or<R::Err, io::Error>>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         use ReadFut::*;
 
         let fd = mem::replace(&mut *self, Done);
-        let Pending(fd) = fd else {
+        let Pending(mut fd) = fd else {
             panic!("Poll on Completed future!");
         };
 
+        match fd.cursor.poll_seek(Pin::new(&mut *fd.reader), cx, fd.pos) {
+            Poll::Pending => {
+                *self = Pending(fd);
+                return Poll::Pending;
+            }
+            Poll::Ready(rs) => rs.map_err(Or::R)?,
+        }
+
         let pin = Pin::new(&mut *fd.reader);
-        let poll = pin.poll_read(cx, fd.buf, fd.pos);
-        if let Poll::Pending = poll {
+        let poll = pin.poll_read(cx, fd.buf);
+        if poll.is_pending() {
             *self = Pending(fd);
         }
 
-        return poll;
+        poll.map_err(Or::L)
     }
 }
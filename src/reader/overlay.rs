@@ -1,14 +1,22 @@
 use std::{
-    io::SeekFrom,
+    io::{self, SeekFrom},
+    ops::Range,
     pin::Pin,
     task::{ready, Context, Poll},
 };
 
-use crate::{buf::DataReadBuf, or::Or};
+use crate::{
+    buf::DataReadBuf,
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
 use pin_project::pin_project;
 use tokio::io::AsyncSeek;
 
-use super::AsyncDataRead;
+use super::{
+    provenance::{stack, under, Layer, LayerPath, Provenance},
+    AsyncDataRead,
+};
 
 #[derive(Debug, Clone, Copy)]
 #[pin_project]
@@ -23,9 +31,10 @@ where
     #[pin]
     overlay: O,
 
+    base_cur: InnerCursor,
+    overlay_cur: InnerCursor,
     cur: u64,
-    seeking: bool,
-    desync: bool,
+    seek_op: Option<SeekFrom>,
 }
 
 impl<T, B, O> OverlaySource<T, B, O>
@@ -37,9 +46,10 @@ where
         Self {
             base,
             overlay,
+            base_cur: InnerCursor::default(),
+            overlay_cur: InnerCursor::default(),
             cur: 0,
-            seeking: false,
-            desync: false,
+            seek_op: None,
         }
     }
 }
@@ -50,7 +60,7 @@ where
     O: AsyncDataRead<Item = T> + AsyncSeek,
 {
     type Item = T;
-    type Err = Or<B::Err, O::Err>;
+    type Err = Or<Or<B::Err, O::Err>, io::Error>;
 
     fn poll_read(
         self: Pin<&mut Self>,
@@ -58,49 +68,87 @@ where
         buf: &mut impl DataReadBuf<Item = Self::Item>,
     ) -> Poll<Result<Option<u64>, Self::Err>> {
         let mut this = self.project();
-        if *this.seeking {
-            ready!(this.base.as_mut().poll_complete(cx));
-            *this.seeking = false;
-            *this.desync = false;
-        }
+        let cur = *this.cur;
 
         // Read the overlay data
+        ready!(this.overlay_cur.poll_seek(this.overlay.as_mut(), cx, cur)).map_err(Or::R)?;
         let prev = buf.filled().len();
         let overlay_next = match ready!(this.overlay.poll_read(cx, buf)) {
-            Err(err) => return Poll::Ready(Err(Or::R(err))),
+            Err(err) => return Poll::Ready(Err(Or::L(Or::R(err)))),
             Ok(x) => x,
         };
-        let cur = buf.filled().len();
-        let wb = cur - prev;
+        let wb = buf.filled().len() - prev;
+        this.overlay_cur.advance(wb as u64);
         *this.cur += wb as u64;
         if wb > 0 {
-            *this.desync = true;
             return Poll::Ready(Ok(Some(*this.cur)));
         }
 
         let unfilled = buf.capacity() - buf.filled().len();
         // Only fill the hole of overlay
-        let limit = overlay_next
-            .map(|x| x - *this.cur)
-            .unwrap_or(unfilled as u64);
-
-        let mut new_buf = buf.take(limit as usize);
-        if *this.desync {
-            this.base.as_mut().start_seek(SeekFrom::Start(*this.cur));
-            *this.seeking = true;
-        }
+        let limit = overlay_next.map(|x| x - cur).unwrap_or(unfilled as u64);
 
+        ready!(this.base_cur.poll_seek(this.base.as_mut(), cx, cur)).map_err(Or::R)?;
+        let mut new_buf = buf.take(limit.min(unfilled as u64) as usize);
         let base_next = match ready!(this.base.as_mut().poll_read(cx, &mut new_buf)) {
-            Err(err) => return Poll::Ready(Err(Or::L(err))),
+            Err(err) => return Poll::Ready(Err(Or::L(Or::L(err)))),
             Ok(x) => x,
         };
         let wb = new_buf.filled().len();
+        this.base_cur.advance(wb as u64);
         if wb > 0 {
             *this.cur += wb as u64;
             return Poll::Ready(Ok(Some(*this.cur)));
-        } else {
-            return Poll::Ready(Ok(overlay_next.min(base_next)));
         }
+
+        let next = match (overlay_next, base_next) {
+            (Some(x), Some(y)) => Some(x.min(y)),
+            (x, y) => x.or(y),
+        };
+        Poll::Ready(Ok(next))
+    }
+}
+
+impl<T, B, O> AsyncSeek for OverlaySource<T, B, O>
+where
+    B: AsyncDataRead<Item = T> + AsyncSeek,
+    O: AsyncDataRead<Item = T> + AsyncSeek,
+{
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        *self.project().seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.project();
+        let Some(op) = *this.seek_op else {
+            return Poll::Ready(Ok(*this.cur));
+        };
+
+        let len = match op {
+            SeekFrom::End(_) => {
+                let base = ready!(this.base_cur.poll_len(this.base, cx))?;
+                let overlay = ready!(this.overlay_cur.poll_len(this.overlay, cx))?;
+                base.max(overlay)
+            }
+            _ => 0,
+        };
+        *this.seek_op = None;
+        *this.cur = op.eval(*this.cur, len)?;
+        Poll::Ready(Ok(*this.cur))
+    }
+}
+
+impl<T, B, O> Provenance for OverlaySource<T, B, O>
+where
+    B: AsyncDataRead<Item = T> + Provenance,
+    O: AsyncDataRead<Item = T> + Provenance,
+{
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        let top = under(Layer::Overlay, self.overlay.provenance(range.clone()));
+        stack(range, top, |hole| {
+            under(Layer::Base, self.base.provenance(hole))
+        })
     }
 }
 
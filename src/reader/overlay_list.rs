@@ -1,6 +1,6 @@
 use std::{
     fmt::Debug,
-    pin::{pin, Pin},
+    pin::Pin,
     task::{ready, Context, Poll},
 };
 
@@ -30,24 +30,23 @@ where
     type Err = F::Err;
 
     fn poll_read(
-        mut self: Pin<&mut Self>,
+        self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         buf: &mut impl DataReadBuf<Item = Self::Item>,
-        pos: usize,
-    ) -> Poll<Result<Option<usize>, Self::Err>> {
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
         let this = self.project();
         let mut unused_buf = buf.take(buf.capacity());
         let mut temp_buf = unused_buf.take(this.cap.unwrap_or(unused_buf.capacity()));
 
         if let Some(r) = this.reader.as_pin_mut() {
-            let next = ready!(r.poll_read(cx, &mut temp_buf, pos))?;
+            let _next = ready!(r.poll_read(cx, &mut temp_buf))?;
         }
 
         if this.iter.is_none() {
             *this.iter = Some(this.tf.produce_iter());
         }
 
-        let iter = this.iter.as_mut().unwrap().next();
+        let _iter = this.iter.as_mut().unwrap().next();
 
         // let mut cap = None;
 
@@ -98,12 +97,11 @@ where
     type Err = F::Err;
 
     fn poll_read(
-        mut self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
         buf: &mut impl DataReadBuf<Item = Self::Item>,
-        pos: usize,
-    ) -> Poll<Result<Option<usize>, Self::Err>> {
-        let mut unused_buf = buf.take(buf.capacity());
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let _unused_buf = buf.take(buf.capacity());
         // let mut cap = None;
 
         // for s in self.tf.produce_iter() {
@@ -119,16 +117,12 @@ where
         // }
 
         // Empty source
-        return Poll::Ready(Ok(None));
+        Poll::Ready(Ok(None))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{buf, reader::overlay_once::OverlayOnce};
-
-    use super::*;
-
     // #[tokio::test]
     // async fn basic() {
     //     let first = OverlayOnce::new(0, [1, 2, 3]).delay(Duration::from_millis(100));
@@ -2,6 +2,7 @@ use std::{
     borrow::Borrow,
     io::{Seek, SeekFrom},
     marker::PhantomData,
+    ops::Range,
     pin::Pin,
     task::{Context, Poll},
 };
@@ -9,7 +10,10 @@ use std::{
 use pin_project::pin_project;
 use tokio::io::AsyncSeek;
 
-use super::AsyncDataRead;
+use super::{
+    provenance::{LayerPath, Provenance},
+    AsyncDataRead,
+};
 use crate::{buf::DataReadBuf, utils::SeekFromExt};
 
 #[derive(Debug, Clone, Copy)]
@@ -48,7 +52,7 @@ impl<T, C: Borrow<[T]>> OverlayOnce<T, C> {
     }
 }
 
-impl<T, C: Borrow<[T]>> AsyncDataRead for OverlayOnce<T, C> {
+impl<T: Clone, C: Borrow<[T]>> AsyncDataRead for OverlayOnce<T, C> {
     type Item = T;
     type Err = ();
 
@@ -57,11 +61,22 @@ impl<T, C: Borrow<[T]>> AsyncDataRead for OverlayOnce<T, C> {
         _cx: &mut Context<'_>,
         buf: &mut impl DataReadBuf<Item = Self::Item>,
     ) -> Poll<Result<Option<u64>, Self::Err>> {
-        let wb = buf.put_slice_guard(&self.data.borrow()[self.cur..]);
+        let wb = buf.put_slice_guard(self.data.borrow().get(self.cur..).unwrap_or(&[]));
         self.cur += wb;
         let rt = if wb == 0 { None } else { Some(self.cur) }.map(|x| x as u64);
 
-        return Poll::Ready(Ok(rt));
+        Poll::Ready(Ok(rt))
+    }
+}
+
+impl<T, C: Borrow<[T]>> Provenance for OverlayOnce<T, C> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        let end = range.end.min(self.data.borrow().len() as u64);
+        if range.start >= end {
+            return Vec::new();
+        }
+
+        vec![(range.start..end, Vec::new())]
     }
 }
 
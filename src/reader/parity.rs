@@ -0,0 +1,429 @@
+use std::{
+    io::{self, SeekFrom},
+    ops::BitXor,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use tokio::io::AsyncSeek;
+
+use super::{stripe::MemberRead, AsyncDataRead};
+use crate::{
+    buf::DataReadBuf,
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
+
+/// Where the parity stripe of every row lives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    /// Always on the given disk (RAID-4)
+    Dedicated(usize),
+    /// Starts on the last disk and moves one disk left every row (RAID-5, left asymmetric)
+    Rotating,
+}
+
+/// How logical positions are laid out across the disks of a parity stripe set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParityLayout {
+    /// Data stripes per row, there is one more disk holding the parity
+    pub data: usize,
+    pub stripe: u64,
+    /// Disk position where the striped data starts
+    pub offset: u64,
+    pub parity: Parity,
+}
+
+impl ParityLayout {
+    pub fn new(data: usize, stripe: u64, parity: Parity) -> Self {
+        assert!(data > 0 && stripe > 0, "empty stripe layout!");
+        if let Parity::Dedicated(k) = parity {
+            assert!(k <= data, "parity disk out of bound!");
+        }
+
+        Self {
+            data,
+            stripe,
+            offset: 0,
+            parity,
+        }
+    }
+
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn disks(&self) -> usize {
+        self.data + 1
+    }
+
+    pub fn parity_disk(&self, row: u64) -> usize {
+        match self.parity {
+            Parity::Dedicated(k) => k,
+            Parity::Rotating => self.data - (row % self.disks() as u64) as usize,
+        }
+    }
+
+    /// Disk holding logical `pos`, the position on that disk and how many items are left in the
+    /// stripe
+    pub fn locate(&self, pos: u64) -> (usize, u64, u64) {
+        let s = pos / self.stripe;
+        let row = s / self.data as u64;
+        let d = (s % self.data as u64) as usize;
+        let pd = self.parity_disk(row);
+        let disk = if d < pd { d } else { d + 1 };
+        let within = pos % self.stripe;
+        (
+            disk,
+            self.offset + row * self.stripe + within,
+            self.stripe - within,
+        )
+    }
+
+    /// First logical position after the row holding `pos`
+    fn row_end(&self, pos: u64) -> u64 {
+        let row_len = self.stripe * self.data as u64;
+        (pos / row_len + 1) * row_len
+    }
+}
+
+#[derive(Debug)]
+struct Rebuild<T> {
+    reads: Vec<Option<MemberRead<T>>>,
+}
+
+/// Read a parity stripe set, rebuilding a single missing or failing disk from the others by XOR.
+/// Create with [`ParityStripe::new`]
+#[derive(Debug)]
+pub struct ParityStripe<R: AsyncDataRead> {
+    disks: Vec<Option<R>>,
+    cursors: Vec<InnerCursor>,
+    layout: ParityLayout,
+    failed: Option<usize>,
+    rebuild: Option<Rebuild<R::Item>>,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+impl<R: AsyncDataRead> ParityStripe<R> {
+    /// `None` marks a missing disk image, at most one disk can be missing
+    pub fn new(disks: Vec<Option<R>>, layout: ParityLayout) -> Self {
+        assert_eq!(disks.len(), layout.disks(), "disk count mismatch!");
+        let mut missing = disks.iter().enumerate().filter(|x| x.1.is_none());
+        let failed = missing.next().map(|x| x.0);
+        assert!(missing.next().is_none(), "more than one disk is missing!");
+
+        Self {
+            cursors: vec![InnerCursor::default(); disks.len()],
+            disks,
+            layout,
+            failed,
+            rebuild: None,
+            cur: 0,
+            seek_op: None,
+        }
+    }
+
+    /// The disk that is being rebuilt from the others, either missing or failed on a read
+    pub fn failed(&self) -> Option<usize> {
+        self.failed
+    }
+
+    pub fn layout(&self) -> ParityLayout {
+        self.layout
+    }
+
+    pub fn into_inner(self) -> Vec<Option<R>> {
+        self.disks
+    }
+
+    /// Next position after a hole that starts at `cur` and ends at `next` on disk, `dpos` and
+    /// `left` being where `cur` is on disk
+    fn after_hole(&self, next: Option<u64>, dpos: u64, left: u64) -> Option<u64> {
+        let cur = self.cur;
+        match next {
+            Some(x) if x < dpos + left => Some(cur + x - dpos),
+            // Nothing left on this disk, only the rest of the row may still have data
+            None if cur + left >= self.layout.row_end(cur) => None,
+            _ => Some(cur + left),
+        }
+    }
+}
+
+impl<R> ParityStripe<R>
+where
+    R: AsyncDataRead + AsyncSeek + Unpin,
+    R::Item: Copy + Unpin + BitXor<Output = R::Item>,
+{
+    fn poll_rebuild(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = R::Item>,
+        disk: usize,
+        dpos: u64,
+        len: u64,
+    ) -> Poll<Result<Option<u64>, Or<R::Err, io::Error>>> {
+        let rebuild = self.rebuild.get_or_insert_with(|| Rebuild {
+            reads: (0..self.disks.len())
+                .map(|j| (j != disk).then(|| MemberRead::new(dpos, len)))
+                .collect(),
+        });
+
+        let mut pending = false;
+        for (j, rd) in rebuild.reads.iter_mut().enumerate() {
+            let (Some(rd), Some(reader)) = (rd, &mut self.disks[j]) else {
+                continue;
+            };
+
+            match rd.poll(Pin::new(reader), &mut self.cursors[j], cx) {
+                Poll::Pending => pending = true,
+                Poll::Ready(Ok(())) => {}
+                // A second failure can't be recovered
+                Poll::Ready(Err(err)) => {
+                    self.rebuild = None;
+                    return Poll::Ready(Err(err));
+                }
+            }
+        }
+
+        if pending {
+            return Poll::Pending;
+        }
+
+        let reads: Vec<_> = self
+            .rebuild
+            .take()
+            .expect("Rebuild in progress")
+            .reads
+            .into_iter()
+            .flatten()
+            .collect();
+        let n = reads
+            .iter()
+            .map(|x| x.buf.filled().len())
+            .min()
+            .unwrap_or(0)
+            // The buffer may have shrunk since the reads were started
+            .min(len as usize);
+        if n == 0 {
+            // Every other disk is needed, so data comes back once the last of them has some
+            let next = reads
+                .iter()
+                .filter(|x| x.buf.filled().is_empty())
+                .map(|x| x.next.flatten())
+                .try_fold(0, |acc, x| x.map(|x| acc.max(x)));
+            return Poll::Ready(Ok(self.after_hole(next, dpos, len)));
+        }
+
+        let data: Vec<_> = (0..n)
+            .map(|i| {
+                reads[1..]
+                    .iter()
+                    .fold(reads[0].buf.filled()[i], |acc, x| acc ^ x.buf.filled()[i])
+            })
+            .collect();
+        buf.put_slice(&data);
+        self.cur += n as u64;
+        Poll::Ready(Ok(Some(self.cur)))
+    }
+}
+
+impl<R> AsyncDataRead for ParityStripe<R>
+where
+    R: AsyncDataRead + AsyncSeek + Unpin,
+    R::Item: Copy + Unpin + BitXor<Output = R::Item>,
+{
+    type Item = R::Item;
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let this = self.get_mut();
+        let (disk, dpos, left) = this.layout.locate(this.cur);
+        let unfilled = (buf.capacity() - buf.filled().len()) as u64;
+        let len = unfilled.min(left);
+
+        if this.rebuild.is_none() && this.failed != Some(disk) {
+            let reader = this.disks[disk]
+                .as_mut()
+                .expect("Only the failed disk is missing");
+            let mut reader = Pin::new(reader);
+            ready!(this.cursors[disk].poll_seek(reader.as_mut(), cx, dpos)).map_err(Or::R)?;
+
+            let mut new_buf = buf.take(len as usize);
+            match ready!(reader.poll_read(cx, &mut new_buf)) {
+                Ok(next) => {
+                    let wb = new_buf.filled().len() as u64;
+                    drop(new_buf);
+                    this.cursors[disk].advance(wb);
+                    if wb > 0 {
+                        this.cur += wb;
+                        return Poll::Ready(Ok(Some(this.cur)));
+                    }
+
+                    return Poll::Ready(Ok(this.after_hole(next, dpos, left)));
+                }
+                Err(_) if this.failed.is_none() => {
+                    // The rebuild replaces whatever the disk wrote before it failed
+                    let wb = new_buf.filled().len();
+                    new_buf.shrink(wb);
+                    drop(new_buf);
+                    this.failed = Some(disk);
+                    this.cursors[disk].reset();
+                }
+                Err(err) => return Poll::Ready(Err(Or::L(err))),
+            }
+        }
+
+        this.poll_rebuild(cx, buf, disk, dpos, len)
+    }
+}
+
+impl<R> AsyncSeek for ParityStripe<R>
+where
+    R: AsyncDataRead + AsyncSeek + Unpin,
+    R::Item: Unpin,
+{
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+        this.rebuild = None;
+        this.seek_op = Some(position);
+        Ok(())
+    }
+
+    // Assumes the last row is complete, every disk but the failed one gives the same length
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        let Some(op) = this.seek_op else {
+            return Poll::Ready(Ok(this.cur));
+        };
+
+        let mut len = 0;
+        if let SeekFrom::End(_) = op {
+            let (k, disk) = this
+                .disks
+                .iter_mut()
+                .enumerate()
+                .find_map(|(k, x)| {
+                    x.as_mut()
+                        .filter(|_| this.failed != Some(k))
+                        .map(|x| (k, x))
+                })
+                .expect("Only one disk can fail");
+            let disk_len = ready!(this.cursors[k].poll_len(Pin::new(disk), cx))?;
+            len = disk_len.saturating_sub(this.layout.offset) * this.layout.data as u64;
+        }
+
+        this.seek_op = None;
+        this.cur = op.eval(this.cur, len)?;
+        Poll::Ready(Ok(this.cur))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::SeekFrom,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use tokio::io::AsyncSeek;
+
+    use super::{Parity, ParityLayout, ParityStripe};
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    /// Fails every read that reaches `fail_at`, after writing the items before it
+    struct Disk {
+        data: Vec<u8>,
+        cur: u64,
+        fail_at: usize,
+    }
+
+    impl AsyncDataRead for Disk {
+        type Item = u8;
+        type Err = &'static str;
+
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut impl DataReadBuf<Item = Self::Item>,
+        ) -> Poll<Result<Option<u64>, Self::Err>> {
+            let end = self.data.len().min(self.fail_at);
+            let wb = buf.put_slice_guard(self.data.get(self.cur as usize..end).unwrap_or(&[]));
+            self.cur += wb as u64;
+            if self.cur as usize >= self.fail_at {
+                return Poll::Ready(Err("bad sector"));
+            }
+
+            Poll::Ready(Ok(Some(self.cur).filter(|_| wb > 0)))
+        }
+    }
+
+    impl AsyncSeek for Disk {
+        fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+            if let SeekFrom::Start(x) = position {
+                self.cur = x;
+            }
+            Ok(())
+        }
+
+        fn poll_complete(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+            Poll::Ready(Ok(self.cur))
+        }
+    }
+
+    #[tokio::test]
+    async fn rebuild_missing() {
+        // Two data disks, rotating parity, stripe of 2. Row 0 parity on disk 2, row 1 on disk 1
+        let d0 = OverlayOnce::new(vec![1u8, 2, 5, 6]);
+        let d1 = OverlayOnce::new(vec![3u8, 4, 5 ^ 7, 6 ^ 8]);
+        let layout = ParityLayout::new(2, 2, Parity::Rotating);
+        let mut source = ParityStripe::new(vec![Some(d0), Some(d1), None], layout);
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(4, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(6));
+        assert_eq!(buf.filled(), &[5, 6]);
+
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(6, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(8));
+        assert_eq!(buf.filled(), &[7, 8]);
+        assert_eq!(source.failed(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn rebuild_partial_failure() {
+        let disk = |data: Vec<u8>, fail_at| Disk {
+            data,
+            cur: 0,
+            fail_at,
+        };
+        let d0 = disk(vec![1, 2, 3, 4], 2);
+        let d1 = disk(vec![5, 6, 7, 8], usize::MAX);
+        let d2 = disk(vec![1 ^ 5, 2 ^ 6, 3 ^ 7, 4 ^ 8], usize::MAX);
+        let layout = ParityLayout::new(2, 4, Parity::Rotating);
+        let mut source = ParityStripe::new(vec![Some(d0), Some(d1), Some(d2)], layout);
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(4));
+        assert_eq!(buf.filled(), &[1, 2, 3, 4]);
+        assert_eq!(source.failed(), Some(0));
+    }
+}
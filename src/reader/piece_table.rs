@@ -0,0 +1,309 @@
+use std::{
+    io::{self, SeekFrom},
+    ops::Range,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use pin_project::pin_project;
+use tokio::io::AsyncSeek;
+
+use super::{
+    provenance::{under, Layer, LayerPath, Provenance},
+    AsyncDataRead,
+};
+use crate::{
+    buf::DataReadBuf,
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Src {
+    Base,
+    Add,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Piece {
+    src: Src,
+    start: u64,
+    len: u64,
+}
+
+/// Editable view of `base`. Unlike overlays, [`insert`](Self::insert) and [`delete`](Self::delete)
+/// move every item after the edit point.
+#[derive(Debug)]
+#[pin_project]
+pub struct PieceTable<R: AsyncDataRead> {
+    #[pin]
+    base: R,
+    base_cur: InnerCursor,
+    add: Vec<R::Item>,
+    pieces: Vec<Piece>,
+    cur: u64,
+}
+
+impl<R: AsyncDataRead> PieceTable<R> {
+    /// `len` is the length of `base`, items past it are never read
+    pub fn new(base: R, len: u64) -> Self {
+        let pieces = if len > 0 {
+            vec![Piece {
+                src: Src::Base,
+                start: 0,
+                len,
+            }]
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            base,
+            base_cur: InnerCursor::default(),
+            add: Vec::new(),
+            pieces,
+            cur: 0,
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.pieces.iter().map(|x| x.len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pieces.is_empty()
+    }
+
+    /// Index of the piece containing `pos` and the offset of `pos` inside it
+    fn locate(&self, pos: u64) -> Option<(usize, u64)> {
+        let mut start = 0;
+        for (i, p) in self.pieces.iter().enumerate() {
+            if pos < start + p.len {
+                return Some((i, pos - start));
+            }
+            start += p.len;
+        }
+
+        None
+    }
+
+    /// Make sure a piece boundary exists at `pos` and return the index of the piece starting there
+    fn split(&mut self, pos: u64) -> usize {
+        let Some((i, off)) = self.locate(pos) else {
+            return self.pieces.len();
+        };
+
+        if off == 0 {
+            return i;
+        }
+
+        let p = self.pieces[i];
+        self.pieces[i].len = off;
+        self.pieces.insert(
+            i + 1,
+            Piece {
+                src: p.src,
+                start: p.start + off,
+                len: p.len - off,
+            },
+        );
+        i + 1
+    }
+
+    pub fn insert(&mut self, pos: u64, data: &[R::Item])
+    where
+        R::Item: Clone,
+    {
+        assert!(pos <= self.len(), "insert position out of bound!");
+        if data.is_empty() {
+            return;
+        }
+
+        let piece = Piece {
+            src: Src::Add,
+            start: self.add.len() as u64,
+            len: data.len() as u64,
+        };
+        self.add.extend_from_slice(data);
+        let i = self.split(pos);
+        self.pieces.insert(i, piece);
+    }
+
+    pub fn delete(&mut self, range: Range<u64>) {
+        let end = range.end.min(self.len());
+        if range.start >= end {
+            return;
+        }
+
+        let first = self.split(range.start);
+        let last = self.split(end);
+        self.pieces.drain(first..last);
+    }
+
+    pub fn replace(&mut self, range: Range<u64>, data: &[R::Item])
+    where
+        R::Item: Clone,
+    {
+        let start = range.start;
+        self.delete(range);
+        self.insert(start, data);
+    }
+}
+
+impl<R> AsyncDataRead for PieceTable<R>
+where
+    R: AsyncDataRead + AsyncSeek,
+    R::Item: Clone,
+{
+    type Item = R::Item;
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let Some((i, off)) = self.locate(self.cur) else {
+            return Poll::Ready(Ok(None));
+        };
+
+        let mut this = self.project();
+        let piece = this.pieces[i];
+        let remain = piece.len - off;
+        match piece.src {
+            Src::Add => {
+                let start = (piece.start + off) as usize;
+                let wb = buf.put_slice_guard(&this.add[start..start + remain as usize]);
+                *this.cur += wb as u64;
+                Poll::Ready(Ok(Some(*this.cur)))
+            }
+            Src::Base => {
+                let base_pos = piece.start + off;
+                ready!(this.base_cur.poll_seek(this.base.as_mut(), cx, base_pos)).map_err(Or::R)?;
+
+                let unfilled = (buf.capacity() - buf.filled().len()) as u64;
+                let mut new_buf = buf.take(unfilled.min(remain) as usize);
+                let next = ready!(this.base.as_mut().poll_read(cx, &mut new_buf)).map_err(Or::L)?;
+                let wb = new_buf.filled().len() as u64;
+                this.base_cur.advance(wb);
+                if wb > 0 {
+                    *this.cur += wb;
+                    return Poll::Ready(Ok(Some(*this.cur)));
+                }
+
+                // Hole in the base, it may end inside this piece or run past it
+                let piece_start = *this.cur - off;
+                let rs = match next {
+                    Some(x) if x < piece.start + piece.len => piece_start + x - piece.start,
+                    _ if i + 1 < this.pieces.len() => piece_start + piece.len,
+                    _ => return Poll::Ready(Ok(None)),
+                };
+                Poll::Ready(Ok(Some(rs)))
+            }
+        }
+    }
+}
+
+impl<R: AsyncDataRead> AsyncSeek for PieceTable<R> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        let len = self.len();
+        let this = self.project();
+        *this.cur = position.eval(*this.cur, len)?;
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+        Poll::Ready(Ok(self.cur))
+    }
+}
+
+impl<R> Provenance for PieceTable<R>
+where
+    R: AsyncDataRead + Provenance,
+{
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        let mut rs = Vec::new();
+        let mut start = 0;
+        for p in &self.pieces {
+            let end = start + p.len;
+            let lo = range.start.max(start);
+            let hi = range.end.min(end);
+            if lo < hi {
+                match p.src {
+                    Src::Add => rs.push((lo..hi, vec![Layer::Edit])),
+                    Src::Base => {
+                        let base_lo = p.start + lo - start;
+                        let spans = self
+                            .base
+                            .provenance(base_lo..base_lo + hi - lo)
+                            .into_iter()
+                            .map(|(r, path)| {
+                                (r.start - p.start + start..r.end - p.start + start, path)
+                            })
+                            .collect();
+                        rs.extend(under(Layer::Base, spans));
+                    }
+                }
+            }
+            start = end;
+        }
+
+        rs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PieceTable;
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    #[tokio::test]
+    async fn insert() {
+        let mut table = PieceTable::new(OverlayOnce::new([1, 2, 3, 4, 5]), 5);
+        table.insert(2, &[100, 101]);
+        assert_eq!(table.len(), 7);
+
+        let mut buf = buf::new::<10, _>();
+        let next = table
+            .read_single_pass(1, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(2));
+        assert_eq!(buf.filled(), &[2]);
+
+        let mut buf = buf::new::<10, _>();
+        let next = table
+            .read_single_pass(2, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(4));
+        assert_eq!(buf.filled(), &[100, 101]);
+    }
+
+    #[tokio::test]
+    async fn delete_replace() {
+        let mut table = PieceTable::new(OverlayOnce::new([1, 2, 3, 4, 5, 6, 7, 8]), 8);
+        table.delete(1..3);
+        table.replace(3..5, &[100]);
+        assert_eq!(table.len(), 5);
+
+        let mut buf = buf::new::<10, _>();
+        let next = table
+            .read_single_pass(1, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(3));
+        assert_eq!(buf.filled(), &[4, 5]);
+
+        let mut buf = buf::new::<10, _>();
+        let next = table
+            .read_single_pass(3, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(4));
+        assert_eq!(buf.filled(), &[100]);
+    }
+}
@@ -0,0 +1,219 @@
+use std::{
+    io::SeekFrom,
+    ops::Range,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use pin_project::pin_project;
+use tokio::io::AsyncSeek;
+
+use super::AsyncDataRead;
+use crate::buf::DataReadBuf;
+
+/// A single step from a composed reader down to one of its children
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    /// The base of an [`OverlaySource`](super::overlay::OverlaySource) or a
+    /// [`PieceTable`](super::piece_table::PieceTable)
+    Base,
+    /// The overlay of an [`OverlaySource`](super::overlay::OverlaySource)
+    Overlay,
+    /// Items inserted into a [`PieceTable`](super::piece_table::PieceTable)
+    Edit,
+    /// The n-th reader of a [`Chain`](super::chain::Chain) or [`Concat`](super::chain::Concat)
+    Part(usize),
+    /// The reader of an [`OrElse`](super::fill::OrElse) whose holes are filled, or the reader of a
+    /// [`Failover`](super::failover::Failover) that is tried first
+    Primary,
+    /// The reader filling the holes of an [`OrElse`](super::fill::OrElse), or the one a
+    /// [`Failover`](super::failover::Failover) retries failed reads on
+    Fallback,
+    /// A hole reported as an item by [`FillHoles`](super::fill::FillHoles)
+    Fill,
+    /// The n-th member of a [`Striped`](super::stripe::Striped) set
+    Member(usize),
+    /// Data went through a [`ShiftLeft`](super::shift::ShiftLeft) by n
+    ShiftLeft(u64),
+    /// Data went through a [`ShiftRight`](super::shift::ShiftRight) by n
+    ShiftRight(u64),
+}
+
+/// Layers from the outermost reader to the source that holds the data
+pub type LayerPath = Vec<Layer>;
+
+pub trait Provenance {
+    /// Sub ranges of `range` that have data, in ascending order, each with the path of the layer
+    /// that supplies it. Positions that are not covered are holes.
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)>;
+}
+
+/// Prepend `layer` to every path in `spans`
+pub fn under(layer: Layer, spans: Vec<(Range<u64>, LayerPath)>) -> Vec<(Range<u64>, LayerPath)> {
+    spans
+        .into_iter()
+        .map(|(r, mut path)| {
+            path.insert(0, layer);
+            (r, path)
+        })
+        .collect()
+}
+
+/// Fill the holes of `top` inside `range` with whatever `lower` reports for them
+pub fn stack(
+    range: Range<u64>,
+    top: Vec<(Range<u64>, LayerPath)>,
+    mut lower: impl FnMut(Range<u64>) -> Vec<(Range<u64>, LayerPath)>,
+) -> Vec<(Range<u64>, LayerPath)> {
+    let mut rs = Vec::with_capacity(top.len());
+    let mut cur = range.start;
+    for (r, path) in top {
+        if cur < r.start {
+            rs.extend(lower(cur..r.start));
+        }
+        cur = r.end;
+        rs.push((r, path));
+    }
+
+    if cur < range.end {
+        rs.extend(lower(cur..range.end));
+    }
+
+    rs
+}
+
+/// Record the provenance of every read next to the data. Create with [`AsyncDataRead::annotated`]
+#[derive(Debug)]
+#[pin_project]
+pub struct Annotated<R> {
+    #[pin]
+    reader: R,
+    cur: u64,
+    last: Vec<(Range<u64>, LayerPath)>,
+}
+
+impl<R> Annotated<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            cur: 0,
+            last: Vec::new(),
+        }
+    }
+
+    /// Provenance of the items written by the last read, empty if it hit a hole
+    pub fn last_provenance(&self) -> &[(Range<u64>, LayerPath)] {
+        &self.last
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R> AsyncDataRead for Annotated<R>
+where
+    R: AsyncDataRead + Provenance,
+{
+    type Item = R::Item;
+    type Err = R::Err;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let mut this = self.project();
+        let prev = buf.filled().len();
+        let next = ready!(this.reader.as_mut().poll_read(cx, buf))?;
+        let wb = (buf.filled().len() - prev) as u64;
+        if wb == 0 {
+            this.last.clear();
+            return Poll::Ready(Ok(next));
+        }
+
+        // After data the reader reports where it stopped, so the items came from just before it
+        let end = next.unwrap_or(*this.cur + wb);
+        *this.last = this.reader.provenance(end - wb..end);
+        *this.cur = end;
+        Poll::Ready(Ok(next))
+    }
+}
+
+impl<R: AsyncSeek> AsyncSeek for Annotated<R> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        self.project().reader.start_seek(position)
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+        let this = self.project();
+        let pos = ready!(this.reader.poll_complete(cx))?;
+        *this.cur = pos;
+        Poll::Ready(Ok(pos))
+    }
+}
+
+impl<R: Provenance> Provenance for Annotated<R> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        self.reader.provenance(range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Layer, Provenance};
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    #[test]
+    fn overlay_shifted() {
+        let source = OverlayOnce::new([0; 10]).overlay(OverlayOnce::new([1, 1]).shift_right(5));
+        let rs = source.provenance(2..8);
+        assert_eq!(
+            rs,
+            vec![
+                (2..5, vec![Layer::Base]),
+                (5..7, vec![Layer::Overlay, Layer::ShiftRight(5)]),
+                (7..8, vec![Layer::Base]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn annotated() {
+        let mut source = OverlayOnce::new([1, 2, 3, 4, 5, 6, 7, 8, 9, 10])
+            .overlay_once(5, &[100, 100])
+            .annotated();
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(5, &mut buf)
+            .await
+            .expect("Read failed!");
+
+        assert_eq!(next, Some(7));
+        assert_eq!(buf.filled(), &[100, 100]);
+        assert_eq!(
+            source.last_provenance(),
+            &[(5..7, vec![Layer::Overlay, Layer::ShiftRight(5)])]
+        );
+
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(5));
+        assert_eq!(source.last_provenance(), &[(0..5, vec![Layer::Base])]);
+
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(7, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(10));
+        assert_eq!(buf.filled(), &[8, 9, 10]);
+        assert_eq!(source.last_provenance(), &[(7..10, vec![Layer::Base])]);
+    }
+}
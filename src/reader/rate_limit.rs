@@ -0,0 +1,225 @@
+use std::{
+    future::Future,
+    io::{self, SeekFrom},
+    ops::Range,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use pin_project::pin_project;
+use tokio::{
+    io::AsyncSeek,
+    time::{self, Instant, Sleep},
+};
+
+use super::{
+    provenance::{LayerPath, Provenance},
+    AsyncDataRead,
+};
+use crate::buf::DataReadBuf;
+
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.last = now;
+    }
+}
+
+/// Budget of items per second, one token per item. Clones share the budget
+#[derive(Debug, Clone)]
+pub struct TokenBucket(Arc<Mutex<Bucket>>);
+
+impl TokenBucket {
+    /// Starts full, with `burst` tokens
+    pub fn new(items_per_sec: f64, burst: u64) -> Self {
+        assert!(items_per_sec > 0.0, "no items per second!");
+        assert!(burst > 0, "empty bucket!");
+        Self(Arc::new(Mutex::new(Bucket {
+            rate: items_per_sec,
+            burst: burst as f64,
+            tokens: burst as f64,
+            last: Instant::now(),
+        })))
+    }
+
+    /// Take up to `want` whole tokens, or when there will be one
+    fn reserve(&self, want: u64) -> Result<u64, Instant> {
+        let mut bucket = self.0.lock().expect("Bucket poisoned");
+        let now = Instant::now();
+        bucket.refill(now);
+        if bucket.tokens >= 1.0 {
+            let n = want.min(bucket.tokens as u64);
+            bucket.tokens -= n as f64;
+            return Ok(n);
+        }
+
+        Err(now + Duration::from_secs_f64((1.0 - bucket.tokens) / bucket.rate))
+    }
+
+    /// Give back reserved tokens that weren't used
+    fn refund(&self, n: u64) {
+        if n > 0 {
+            let mut bucket = self.0.lock().expect("Bucket poisoned");
+            bucket.tokens = (bucket.tokens + n as f64).min(bucket.burst);
+        }
+    }
+}
+
+/// Cap the items read per second, reads are cut to the tokens there are and wait for at least
+/// one. Create with [`AsyncDataRead::rate_limit`] or [`AsyncDataRead::rate_limit_with`]
+///
+/// Only items read are paid for, holes are free. Tokens are taken before the inner read and
+/// what it doesn't fill is given back once it's done.
+#[derive(Debug)]
+#[pin_project]
+pub struct RateLimit<R> {
+    #[pin]
+    reader: R,
+    bucket: TokenBucket,
+    #[pin]
+    sleep: Option<Sleep>,
+    /// Held by the read in progress
+    reserved: u64,
+}
+
+impl<R> RateLimit<R> {
+    pub fn new(reader: R, bucket: TokenBucket) -> Self {
+        Self {
+            reader,
+            bucket,
+            sleep: None,
+            reserved: 0,
+        }
+    }
+
+    /// Clone it to put other readers on the same budget
+    pub fn bucket(&self) -> &TokenBucket {
+        &self.bucket
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncDataRead> AsyncDataRead for RateLimit<R> {
+    type Item = R::Item;
+    type Err = R::Err;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let mut this = self.project();
+        let unfilled = buf.capacity() - buf.filled().len();
+        if unfilled == 0 {
+            return this.reader.poll_read(cx, buf);
+        }
+
+        while *this.reserved == 0 {
+            if let Some(sleep) = this.sleep.as_mut().as_pin_mut() {
+                ready!(sleep.poll(cx));
+                this.sleep.set(None);
+            }
+
+            match this.bucket.reserve(unfilled as u64) {
+                Ok(x) => *this.reserved = x,
+                Err(at) => this.sleep.set(Some(time::sleep_until(at))),
+            }
+        }
+
+        let mut part = buf.take(*this.reserved as usize);
+        let rs = ready!(this.reader.poll_read(cx, &mut part));
+        let n = part.filled().len() as u64;
+        this.bucket.refund(std::mem::take(this.reserved) - n);
+        Poll::Ready(rs)
+    }
+}
+
+impl<R: AsyncSeek> AsyncSeek for RateLimit<R> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.project();
+        // The read in progress is abandoned
+        this.bucket.refund(std::mem::take(this.reserved));
+        this.reader.start_seek(position)
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        self.project().reader.poll_complete(cx)
+    }
+}
+
+impl<R: Provenance> Provenance for RateLimit<R> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        self.reader.provenance(range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use super::TokenBucket;
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn shared_bucket() {
+        let bucket = TokenBucket::new(100.0, 2);
+        let a = OverlayOnce::new([1, 2, 3, 4]).rate_limit_with(bucket.clone());
+        let b = OverlayOnce::new([5, 6]).rate_limit_with(bucket);
+        tokio::pin!(a, b);
+        let mut buf = buf::new::<4, _>();
+        let next = a.read_single_pass(0, &mut buf).await.expect("Read failed!");
+        assert_eq!(next, Some(2));
+        assert_eq!(buf.filled(), &[1, 2]);
+
+        // `a` used up the budget, `b` waits for the next token
+        let start = Instant::now();
+        let mut buf = buf::new::<4, _>();
+        let next = b.read_single_pass(0, &mut buf).await.expect("Read failed!");
+        assert_eq!(start.elapsed(), Duration::from_millis(10));
+        assert_eq!(next, Some(1));
+        assert_eq!(buf.filled(), &[5]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn concurrent_reads() {
+        // Both readers are waiting on their inner reads at once, only one gets the budget
+        let bucket = TokenBucket::new(100.0, 2);
+        let delay = Duration::from_millis(1);
+        let a = OverlayOnce::new([1, 2, 3, 4])
+            .delay(delay)
+            .rate_limit_with(bucket.clone());
+        let b = OverlayOnce::new([5, 6])
+            .delay(delay)
+            .rate_limit_with(bucket);
+        tokio::pin!(a, b);
+        let mut buf_a = buf::new::<4, _>();
+        let mut buf_b = buf::new::<4, _>();
+        let (next_a, next_b) = tokio::join!(
+            a.read_single_pass(0, &mut buf_a),
+            b.read_single_pass(0, &mut buf_b),
+        );
+        assert_eq!(next_a.expect("Read failed!"), Some(2));
+        assert_eq!(buf_a.filled(), &[1, 2]);
+        assert_eq!(next_b.expect("Read failed!"), Some(1));
+        assert_eq!(buf_b.filled(), &[5]);
+    }
+}
@@ -0,0 +1,385 @@
+use std::{
+    cmp::Ordering,
+    io::{self, ErrorKind, SeekFrom},
+    ops::Range,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use pin_project::pin_project;
+use tokio::io::{AsyncSeek, AsyncSeekExt};
+
+use super::{
+    provenance::{LayerPath, Provenance},
+    AsyncDataRead,
+};
+use crate::{
+    buf::{self, DataReadBuf, Staging},
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
+
+/// Position and partly read record shared by [`Records`], [`DynRecords`] and the numeric casts
+#[derive(Debug)]
+pub(super) struct RecordCursor<T> {
+    size: u64,
+    inner: InnerCursor,
+    /// Start of the record at `cur`, it is complete once `size` items are in
+    partial: Vec<T>,
+    stage: Staging<T>,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+impl<T: Clone> RecordCursor<T> {
+    pub(super) fn new(size: u64) -> Self {
+        assert!(size > 0, "empty records!");
+        Self {
+            size,
+            inner: InnerCursor::default(),
+            partial: Vec::new(),
+            stage: Staging::default(),
+            cur: 0,
+            seek_op: None,
+        }
+    }
+
+    /// Read up to `n` whole records, a record with any hole in it is a hole
+    pub(super) fn poll_read<R>(
+        &mut self,
+        mut reader: Pin<&mut R>,
+        cx: &mut Context<'_>,
+        n: u64,
+        mut emit: impl FnMut(&[T]),
+    ) -> Poll<Result<Option<u64>, Or<R::Err, io::Error>>>
+    where
+        R: AsyncDataRead<Item = T> + AsyncSeek,
+    {
+        if n == 0 {
+            return Poll::Ready(Ok(Some(self.cur)));
+        }
+
+        let size = self.size;
+        loop {
+            let pos = self.cur * size + self.partial.len() as u64;
+            ready!(self.inner.poll_seek(reader.as_mut(), cx, pos)).map_err(Or::R)?;
+            let mut stage = self.stage.get((n * size) as usize - self.partial.len());
+            let next = ready!(reader.as_mut().poll_read(cx, &mut stage)).map_err(Or::L)?;
+            let wb = stage.filled().len() as u64;
+            self.inner.advance(wb);
+            if wb == 0 {
+                // The record that was started falls in the hole as well
+                self.partial.clear();
+                return Poll::Ready(Ok(next.map(|x| x.div_ceil(size))));
+            }
+
+            self.partial.extend(stage.filled().iter().cloned());
+            let count = self.partial.len() / size as usize;
+            if count == 0 {
+                continue;
+            }
+
+            for record in self.partial.chunks_exact(size as usize) {
+                emit(record);
+            }
+            self.partial.drain(..count * size as usize);
+            self.cur += count as u64;
+            return Poll::Ready(Ok(Some(self.cur)));
+        }
+    }
+
+    pub(super) fn start_seek(&mut self, position: SeekFrom) {
+        self.partial.clear();
+        self.seek_op = Some(position);
+    }
+
+    pub(super) fn poll_complete<R: AsyncSeek>(
+        &mut self,
+        reader: Pin<&mut R>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<u64>> {
+        let Some(op) = self.seek_op else {
+            return Poll::Ready(Ok(self.cur));
+        };
+
+        // A trailing partial record doesn't count
+        let len = match op {
+            SeekFrom::End(_) => ready!(self.inner.poll_len(reader, cx))? / self.size,
+            _ => 0,
+        };
+        self.seek_op = None;
+        self.cur = op.eval(self.cur, len)?;
+        Poll::Ready(Ok(self.cur))
+    }
+
+    pub(super) fn provenance<R: Provenance>(
+        &self,
+        reader: &R,
+        range: Range<u64>,
+    ) -> Vec<(Range<u64>, LayerPath)> {
+        let size = self.size;
+        reader
+            .provenance(range.start * size..range.end * size)
+            .into_iter()
+            .filter_map(|(r, path)| {
+                let r = r.start.div_ceil(size)..r.end / size;
+                (r.start < r.end).then_some((r, path))
+            })
+            .collect()
+    }
+}
+
+/// Binary search records `0..len` sorted by `f`, reading one record per step
+async fn search_by_key<R, K, F>(
+    reader: &mut R,
+    key: &K,
+    mut f: F,
+) -> Result<Result<u64, u64>, Or<R::Err, io::Error>>
+where
+    R: AsyncDataRead + AsyncSeek + Unpin,
+    R::Item: Clone,
+    K: Ord,
+    F: FnMut(&R::Item) -> K,
+{
+    let len = reader.seek(SeekFrom::End(0)).await.map_err(Or::R)?;
+    let (mut lo, mut hi) = (0, len);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        let mut buf = buf::new::<1, _>();
+        reader.read_single_pass(mid, &mut buf).await?;
+        let Some(record) = buf.filled().first() else {
+            let err = io::Error::new(ErrorKind::InvalidData, "Hole in a sorted table!");
+            return Err(Or::R(err));
+        };
+
+        match f(record).cmp(key) {
+            Ordering::Less => lo = mid + 1,
+            Ordering::Greater => hi = mid,
+            Ordering::Equal => return Ok(Ok(mid)),
+        }
+    }
+
+    Ok(Err(lo))
+}
+
+/// Group every `N` items into a record, record `i` starts at item `i * N`. Create with
+/// [`AsyncDataRead::records`]
+#[derive(Debug)]
+#[pin_project]
+pub struct Records<R: AsyncDataRead, const N: usize> {
+    #[pin]
+    reader: R,
+    cursor: RecordCursor<R::Item>,
+}
+
+impl<R: AsyncDataRead, const N: usize> Records<R, N>
+where
+    R::Item: Clone,
+{
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            cursor: RecordCursor::new(N as u64),
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R, const N: usize> Records<R, N>
+where
+    R: AsyncDataRead + AsyncSeek + Unpin,
+    R::Item: Copy,
+{
+    /// Find a record by the key it's sorted by, `Err` is where it would be inserted like
+    /// [`slice::binary_search_by_key`]. Only the visited records are read
+    pub async fn binary_search_by_key<K: Ord>(
+        &mut self,
+        key: &K,
+        f: impl FnMut(&[R::Item; N]) -> K,
+    ) -> Result<Result<u64, u64>, Or<R::Err, io::Error>> {
+        search_by_key(self, key, f).await.map_err(Or::flatten)
+    }
+}
+
+impl<R, const N: usize> AsyncDataRead for Records<R, N>
+where
+    R: AsyncDataRead + AsyncSeek,
+    R::Item: Copy,
+{
+    type Item = [R::Item; N];
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let this = self.project();
+        let n = (buf.capacity() - buf.filled().len()) as u64;
+        this.cursor.poll_read(this.reader, cx, n, |record| {
+            let record = record.try_into().expect("Record of N items");
+            buf.put_slice(&[record]);
+        })
+    }
+}
+
+impl<R, const N: usize> AsyncSeek for Records<R, N>
+where
+    R: AsyncDataRead + AsyncSeek,
+    R::Item: Clone,
+{
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        self.project().cursor.start_seek(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.project();
+        this.cursor.poll_complete(this.reader, cx)
+    }
+}
+
+impl<R, const N: usize> Provenance for Records<R, N>
+where
+    R: AsyncDataRead + Provenance,
+    R::Item: Clone,
+{
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        self.cursor.provenance(&self.reader, range)
+    }
+}
+
+/// [`Records`] with the record size picked at runtime. Create with
+/// [`AsyncDataRead::records_of`]
+#[derive(Debug)]
+#[pin_project]
+pub struct DynRecords<R: AsyncDataRead> {
+    #[pin]
+    reader: R,
+    cursor: RecordCursor<R::Item>,
+}
+
+impl<R: AsyncDataRead> DynRecords<R>
+where
+    R::Item: Clone,
+{
+    pub fn new(reader: R, size: u64) -> Self {
+        Self {
+            reader,
+            cursor: RecordCursor::new(size),
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        self.cursor.size
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R> DynRecords<R>
+where
+    R: AsyncDataRead + AsyncSeek + Unpin,
+    R::Item: Clone,
+{
+    /// See [`Records::binary_search_by_key`]
+    pub async fn binary_search_by_key<K: Ord>(
+        &mut self,
+        key: &K,
+        mut f: impl FnMut(&[R::Item]) -> K,
+    ) -> Result<Result<u64, u64>, Or<R::Err, io::Error>> {
+        search_by_key(self, key, |x: &Vec<_>| f(x))
+            .await
+            .map_err(Or::flatten)
+    }
+}
+
+impl<R> AsyncDataRead for DynRecords<R>
+where
+    R: AsyncDataRead + AsyncSeek,
+    R::Item: Clone,
+{
+    type Item = Vec<R::Item>;
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let this = self.project();
+        let n = (buf.capacity() - buf.filled().len()) as u64;
+        this.cursor.poll_read(this.reader, cx, n, |record| {
+            buf.put_slice(&[record.to_vec()])
+        })
+    }
+}
+
+impl<R> AsyncSeek for DynRecords<R>
+where
+    R: AsyncDataRead + AsyncSeek,
+    R::Item: Clone,
+{
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        self.project().cursor.start_seek(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.project();
+        this.cursor.poll_complete(this.reader, cx)
+    }
+}
+
+impl<R> Provenance for DynRecords<R>
+where
+    R: AsyncDataRead + Provenance,
+    R::Item: Clone,
+{
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        self.cursor.provenance(&self.reader, range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    #[tokio::test]
+    async fn records() {
+        let mut source = OverlayOnce::new([1u8, 2, 3, 4, 5, 6, 7]).records::<2>();
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(1, &mut buf)
+            .await
+            .expect("Read failed!");
+
+        assert_eq!(next, Some(3));
+        assert_eq!(buf.filled(), &[[3, 4], [5, 6]]);
+    }
+
+    #[tokio::test]
+    async fn binary_search() {
+        let table: Vec<u8> = (0..100u8).flat_map(|x| [x * 2, 0xff]).collect();
+        let mut source = OverlayOnce::new(table).records::<2>();
+
+        let found = source.binary_search_by_key(&42, |x| x[0]).await;
+        assert_eq!(found.expect("Search failed!"), Ok(21));
+
+        let found = source.binary_search_by_key(&43, |x| x[0]).await;
+        assert_eq!(found.expect("Search failed!"), Err(22));
+
+        let table: Vec<u8> = (0..100u8).flat_map(|x| [x * 2, 0xff, 0xff]).collect();
+        let mut source = OverlayOnce::new(table).records_of(3);
+        let found = source.binary_search_by_key(&42, |x| x[0]).await;
+        assert_eq!(found.expect("Search failed!"), Ok(21));
+    }
+}
@@ -0,0 +1,249 @@
+use std::{
+    io::{self, ErrorKind, SeekFrom},
+    ops::Range,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use pin_project::pin_project;
+use tokio::io::AsyncSeek;
+
+use super::{
+    provenance::{LayerPath, Provenance},
+    AsyncDataRead,
+};
+use crate::{
+    buf::DataReadBuf,
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
+
+/// Present the first `period` items of a reader over and over. Create with
+/// [`AsyncDataRead::repeat`] or [`AsyncDataRead::cycle`]
+#[derive(Debug)]
+#[pin_project]
+pub struct Repeat<R> {
+    #[pin]
+    reader: R,
+    inner: InnerCursor,
+    period: Option<u64>,
+    times: Option<u64>,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+impl<R> Repeat<R> {
+    /// `None` period repeats the whole reader, its length is found by seeking to the end
+    pub fn new(reader: R, period: Option<u64>) -> Self {
+        Self {
+            reader,
+            inner: InnerCursor::default(),
+            period,
+            times: None,
+            cur: 0,
+            seek_op: None,
+        }
+    }
+
+    /// Stop after `n` periods instead of repeating forever
+    pub fn times(mut self, n: u64) -> Self {
+        self.times = Some(n);
+        self
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncSeek> Repeat<R> {
+    fn poll_period(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.project();
+        if let Some(period) = *this.period {
+            return Poll::Ready(Ok(period));
+        }
+
+        let period = ready!(this.inner.poll_len(this.reader, cx))?;
+        *this.period = Some(period);
+        Poll::Ready(Ok(period))
+    }
+}
+
+impl<R> AsyncDataRead for Repeat<R>
+where
+    R: AsyncDataRead + AsyncSeek,
+{
+    type Item = R::Item;
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let period = ready!(self.as_mut().poll_period(cx)).map_err(Or::R)?;
+        let mut this = self.project();
+        let total = this
+            .times
+            .map(|n| n.saturating_mul(period))
+            .unwrap_or(u64::MAX);
+        let cur = *this.cur;
+        if period == 0 || cur >= total {
+            return Poll::Ready(Ok(None));
+        }
+
+        let off = cur % period;
+        let base = cur - off;
+        ready!(this.inner.poll_seek(this.reader.as_mut(), cx, off)).map_err(Or::R)?;
+
+        // Reads never cross into the next period
+        let unfilled = (buf.capacity() - buf.filled().len()) as u64;
+        let limit = unfilled.min(period - off).min(total - cur);
+        let mut new_buf = buf.take(limit as usize);
+        let next = ready!(this.reader.poll_read(cx, &mut new_buf)).map_err(Or::L)?;
+        let wb = new_buf.filled().len() as u64;
+        this.inner.advance(wb);
+        if wb > 0 {
+            *this.cur += wb;
+            return Poll::Ready(Ok(Some(*this.cur)));
+        }
+
+        let next = match next {
+            Some(x) if x < period => base + x,
+            // The hole runs to the end of the period, so it is repeated at the start of the next one
+            _ => base.saturating_add(period),
+        };
+        Poll::Ready(Ok(Some(next).filter(|x| *x < total)))
+    }
+}
+
+impl<R: AsyncSeek> AsyncSeek for Repeat<R> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        *self.project().seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let Some(op) = self.seek_op else {
+            return Poll::Ready(Ok(self.cur));
+        };
+
+        let total = match op {
+            SeekFrom::End(_) => {
+                let period = ready!(self.as_mut().poll_period(cx))?;
+                let times = self.times.ok_or_else(|| {
+                    io::Error::new(ErrorKind::InvalidInput, "Infinite reader has no end!")
+                })?;
+                times
+                    .checked_mul(period)
+                    .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "Reader is too long!"))?
+            }
+            _ => 0,
+        };
+
+        let this = self.project();
+        *this.seek_op = None;
+        *this.cur = op.eval(*this.cur, total)?;
+        Poll::Ready(Ok(*this.cur))
+    }
+}
+
+// Only answers once the period is known, either given or found by a read or seek
+impl<R: Provenance> Provenance for Repeat<R> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        let Some(period) = self.period.filter(|x| *x > 0) else {
+            return Vec::new();
+        };
+
+        let total = self
+            .times
+            .map(|n| n.saturating_mul(period))
+            .unwrap_or(u64::MAX);
+        let end = range.end.min(total);
+        let mut rs = Vec::new();
+        let mut base = range.start - range.start % period;
+        while base < end {
+            let lo = range.start.max(base) - base;
+            let hi = end.min(base.saturating_add(period)) - base;
+            let spans = self.reader.provenance(lo..hi);
+            rs.extend(
+                spans
+                    .into_iter()
+                    .map(|(r, path)| (r.start + base..r.end + base, path)),
+            );
+            base = match base.checked_add(period) {
+                Some(x) => x,
+                None => break,
+            };
+        }
+
+        rs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{ErrorKind, SeekFrom};
+
+    use tokio::io::AsyncSeekExt;
+
+    use crate::{
+        buf::{self, DataReadBuf},
+        or::Or,
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    #[tokio::test]
+    async fn cycle() {
+        let mut source = OverlayOnce::new([1, 2, 3]).cycle();
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(7, &mut buf)
+            .await
+            .expect("Read failed!");
+
+        assert_eq!(next, Some(9));
+        assert_eq!(buf.filled(), &[2, 3]);
+    }
+
+    #[tokio::test]
+    async fn repeat_times() {
+        let mut source = OverlayOnce::new([1, 2, 3, 4]).repeat(2).times(2);
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(2, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(4));
+        assert_eq!(buf.filled(), &[1, 2]);
+
+        let next = source
+            .read_single_pass(4, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, None);
+    }
+
+    #[tokio::test]
+    async fn too_long() {
+        let mut source = OverlayOnce::new([1, 2]).repeat(2).times(u64::MAX);
+        let err = source
+            .seek(SeekFrom::End(0))
+            .await
+            .expect_err("Seek should fail!");
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[tokio::test]
+    async fn period_error() {
+        // The inner cycle never ends, so there is no period to repeat
+        let mut source = OverlayOnce::new([1, 2]).cycle().cycle();
+        let mut buf = buf::new::<10, _>();
+        let rs = source.read_single_pass(0, &mut buf).await;
+        let Err(Or::L(Or::R(err))) = rs else {
+            panic!("Length error expected!");
+        };
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(buf.filled().is_empty());
+    }
+}
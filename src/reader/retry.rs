@@ -0,0 +1,341 @@
+use std::{
+    future::Future,
+    io::{self, SeekFrom},
+    ops::Range,
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use pin_project::pin_project;
+use tokio::{
+    io::AsyncSeek,
+    time::{self, Sleep},
+};
+
+use super::{
+    provenance::{LayerPath, Provenance},
+    AsyncDataRead,
+};
+use crate::{
+    buf::DataReadBuf,
+    or::Or,
+    utils::{InnerCursor, SeekFromExt, SplitMix64},
+};
+
+/// How often and how long [`Retry`] waits before trying a read again
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Tries per read, the first one included
+    pub max_attempts: u32,
+    /// Wait before the first retry, doubled for every retry after it
+    pub base: Duration,
+    pub max_delay: Duration,
+    /// Up to this fraction of each wait is taken off at random
+    pub jitter: f64,
+    pub seed: u64,
+}
+
+impl RetryPolicy {
+    /// Backoff from 10ms up to 1s, without jitter
+    pub fn new(max_attempts: u32) -> Self {
+        assert!(max_attempts > 0, "no attempts!");
+        Self {
+            max_attempts,
+            base: Duration::from_millis(10),
+            max_delay: Duration::from_secs(1),
+            jitter: 0.0,
+            seed: 0,
+        }
+    }
+
+    pub fn backoff(self, base: Duration, max_delay: Duration) -> Self {
+        Self {
+            base,
+            max_delay,
+            ..self
+        }
+    }
+
+    pub fn jitter(self, jitter: f64, seed: u64) -> Self {
+        assert!((0.0..=1.0).contains(&jitter), "jitter out of range!");
+        Self {
+            jitter,
+            seed,
+            ..self
+        }
+    }
+
+    /// Wait before retry `n`, counting from 0
+    fn delay(&self, n: u32, rng: &mut SplitMix64) -> Duration {
+        let delay = self
+            .base
+            .saturating_mul(2u32.saturating_pow(n))
+            .min(self.max_delay);
+        delay.mul_f64(1.0 - self.jitter * rng.next_f64())
+    }
+}
+
+/// Try failed reads again after a backoff. Create with [`AsyncDataRead::retry`]
+///
+/// Items written before a read failed are kept and the retry picks up after them. When a read
+/// runs out of attempts after writing some items it returns those, the next read starts over at
+/// the failing position.
+#[derive(Debug)]
+#[pin_project]
+pub struct Retry<R, Pr> {
+    #[pin]
+    reader: R,
+    inner: InnerCursor,
+    policy: RetryPolicy,
+    pred: Pr,
+    rng: SplitMix64,
+    /// Failed attempts of the read in progress
+    attempts: u32,
+    written: u64,
+    #[pin]
+    backoff: Option<Sleep>,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+impl<R: AsyncDataRead> Retry<R, fn(&R::Err) -> bool> {
+    /// Every error is retried
+    pub fn new(reader: R, policy: RetryPolicy) -> Self {
+        Self {
+            reader,
+            inner: InnerCursor::default(),
+            policy,
+            pred: |_| true,
+            rng: SplitMix64::new(policy.seed),
+            attempts: 0,
+            written: 0,
+            backoff: None,
+            cur: 0,
+            seek_op: None,
+        }
+    }
+}
+
+impl<R: AsyncDataRead, Pr> Retry<R, Pr> {
+    /// Only retry errors matching `pred`, others are returned as is
+    pub fn when<Q: FnMut(&R::Err) -> bool>(self, pred: Q) -> Retry<R, Q> {
+        Retry {
+            reader: self.reader,
+            inner: self.inner,
+            policy: self.policy,
+            pred,
+            rng: self.rng,
+            attempts: self.attempts,
+            written: self.written,
+            backoff: None,
+            cur: self.cur,
+            seek_op: self.seek_op,
+        }
+    }
+
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R, Pr> AsyncDataRead for Retry<R, Pr>
+where
+    R: AsyncDataRead + AsyncSeek,
+    Pr: FnMut(&R::Err) -> bool,
+{
+    type Item = R::Item;
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let mut this = self.project();
+        loop {
+            if let Some(backoff) = this.backoff.as_mut().as_pin_mut() {
+                ready!(backoff.poll(cx));
+                this.backoff.set(None);
+            }
+
+            // A failed seek isn't retried, the predicate only sees errors of the reader
+            if let Err(err) = ready!(this.inner.poll_seek(this.reader.as_mut(), cx, *this.cur)) {
+                *this.attempts = 0;
+                return match std::mem::take(this.written) {
+                    0 => Poll::Ready(Err(Or::R(err))),
+                    _ => Poll::Ready(Ok(Some(*this.cur))),
+                };
+            }
+
+            let before = buf.filled().len();
+            let rs = ready!(this.reader.as_mut().poll_read(cx, buf));
+            let n = (buf.filled().len() - before) as u64;
+            this.inner.advance(n);
+            *this.cur += n;
+            *this.written += n;
+
+            let err = match rs {
+                Ok(next) => {
+                    *this.attempts = 0;
+                    *this.written = 0;
+                    return Poll::Ready(Ok(next));
+                }
+                Err(err) => err,
+            };
+
+            // The reader may have been anywhere when it failed
+            this.inner.reset();
+            *this.attempts += 1;
+            if *this.attempts >= this.policy.max_attempts || !(this.pred)(&err) {
+                *this.attempts = 0;
+                return match std::mem::take(this.written) {
+                    0 => Poll::Ready(Err(Or::L(err))),
+                    _ => Poll::Ready(Ok(Some(*this.cur))),
+                };
+            }
+
+            let delay = this.policy.delay(*this.attempts - 1, this.rng);
+            this.backoff.set(Some(time::sleep(delay)));
+        }
+    }
+}
+
+impl<R: AsyncDataRead + AsyncSeek, Pr> AsyncSeek for Retry<R, Pr> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let mut this = self.project();
+        this.backoff.set(None);
+        *this.attempts = 0;
+        *this.written = 0;
+        *this.seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.project();
+        let Some(op) = *this.seek_op else {
+            return Poll::Ready(Ok(*this.cur));
+        };
+
+        let len = match op {
+            SeekFrom::End(_) => ready!(this.inner.poll_len(this.reader, cx))?,
+            _ => 0,
+        };
+        *this.seek_op = None;
+        *this.cur = op.eval(*this.cur, len)?;
+        Poll::Ready(Ok(*this.cur))
+    }
+}
+
+impl<R: Provenance, Pr> Provenance for Retry<R, Pr> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        self.reader.provenance(range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::SeekFrom,
+        pin::Pin,
+        task::{Context, Poll},
+        time::Duration,
+    };
+
+    use tokio::{io::AsyncSeek, time::Instant};
+
+    use super::RetryPolicy;
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::AsyncDataRead,
+    };
+
+    /// Writes one item then fails, `fails` times
+    struct Flaky {
+        data: Vec<u8>,
+        cur: u64,
+        fails: u32,
+    }
+
+    impl AsyncDataRead for Flaky {
+        type Item = u8;
+        type Err = &'static str;
+
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut impl DataReadBuf<Item = Self::Item>,
+        ) -> Poll<Result<Option<u64>, Self::Err>> {
+            let rest = &self.data[self.cur as usize..];
+            if self.fails > 0 {
+                buf.put_slice(&rest[..1]);
+                self.fails -= 1;
+                return Poll::Ready(Err("flaky"));
+            }
+
+            let wb = buf.put_slice_guard(rest) as u64;
+            self.cur += wb;
+            Poll::Ready(Ok(Some(self.cur)))
+        }
+    }
+
+    impl AsyncSeek for Flaky {
+        fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+            if let SeekFrom::Start(x) = position {
+                self.cur = x;
+            }
+            Ok(())
+        }
+
+        fn poll_complete(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+            Poll::Ready(Ok(self.cur))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retry() {
+        let flaky = Flaky {
+            data: vec![1, 2, 3, 4, 5],
+            cur: 0,
+            fails: 2,
+        };
+        let policy =
+            RetryPolicy::new(3).backoff(Duration::from_millis(1), Duration::from_millis(2));
+        let source = flaky.retry(policy);
+        tokio::pin!(source);
+        let start = Instant::now();
+        let mut buf = buf::new::<4, _>();
+        let next = source
+            .read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(4));
+        assert_eq!(buf.filled(), &[1, 2, 3, 4]);
+        // Waited 1ms then 2ms
+        assert_eq!(start.elapsed(), Duration::from_millis(3));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn give_up() {
+        let flaky = Flaky {
+            data: vec![1, 2, 3, 4, 5],
+            cur: 0,
+            fails: 2,
+        };
+        let policy =
+            RetryPolicy::new(3).backoff(Duration::from_millis(1), Duration::from_millis(2));
+        let source = flaky.retry(policy).when(|_| false);
+        tokio::pin!(source);
+        let mut buf = buf::new::<4, _>();
+        let next = source
+            .read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(1));
+        assert_eq!(buf.filled(), &[1]);
+    }
+}
@@ -0,0 +1,293 @@
+use std::{
+    io::{self, SeekFrom},
+    ops::Range,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use pin_project::pin_project;
+use tokio::io::AsyncSeek;
+
+use super::{
+    provenance::{LayerPath, Provenance},
+    AsyncDataRead,
+};
+use crate::{
+    buf::{DataReadBuf, Staging},
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
+
+/// Inner range backing one read, scanned from its start
+#[derive(Debug)]
+struct Window<T> {
+    start: u64,
+    end: u64,
+    pos: u64,
+    /// Last run of data seen, it ends at `end` if the read starts with data
+    run_start: u64,
+    run: Vec<T>,
+}
+
+/// Read a finite reader back to front, position `p` is `len - 1 - p` of the inner reader. Create
+/// with [`AsyncDataRead::reverse`]
+#[derive(Debug)]
+#[pin_project]
+pub struct Reverse<R: AsyncDataRead> {
+    #[pin]
+    reader: R,
+    inner: InnerCursor,
+    len: Option<u64>,
+    window: Option<Window<R::Item>>,
+    stage: Staging<R::Item>,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+impl<R: AsyncDataRead> Reverse<R> {
+    /// `None` length is found by seeking to the end of `reader`
+    pub fn new(reader: R, len: Option<u64>) -> Self {
+        Self {
+            reader,
+            inner: InnerCursor::default(),
+            len,
+            window: None,
+            stage: Staging::default(),
+            cur: 0,
+            seek_op: None,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncDataRead + AsyncSeek> Reverse<R> {
+    fn poll_len(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.project();
+        if let Some(len) = *this.len {
+            return Poll::Ready(Ok(len));
+        }
+
+        let len = ready!(this.inner.poll_len(this.reader, cx))?;
+        *this.len = Some(len);
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl<R> AsyncDataRead for Reverse<R>
+where
+    R: AsyncDataRead + AsyncSeek,
+    R::Item: Clone,
+{
+    type Item = R::Item;
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let len = ready!(self.as_mut().poll_len(cx)).map_err(Or::R)?;
+        let mut this = self.project();
+        let cur = *this.cur;
+        let unfilled = (buf.capacity() - buf.filled().len()) as u64;
+        if cur >= len {
+            return Poll::Ready(Ok(None));
+        } else if unfilled == 0 {
+            return Poll::Ready(Ok(Some(cur)));
+        }
+
+        // A window left by a shorter read is only good for the position right after it
+        if this.window.as_ref().is_some_and(|x| x.end != len - cur) {
+            *this.window = None;
+        }
+
+        let win = this.window.get_or_insert_with(|| {
+            let end = len - cur;
+            let start = end - unfilled.min(end);
+            Window {
+                start,
+                end,
+                pos: start,
+                run_start: start,
+                run: Vec::new(),
+            }
+        });
+
+        // The items wanted first are at the end of the window, so all of it has to be read
+        while win.pos < win.end {
+            ready!(this.inner.poll_seek(this.reader.as_mut(), cx, win.pos)).map_err(Or::R)?;
+            let mut stage = this.stage.get((win.end - win.pos) as usize);
+            let next = match ready!(this.reader.as_mut().poll_read(cx, &mut stage)) {
+                Ok(x) => x,
+                Err(err) => {
+                    *this.window = None;
+                    return Poll::Ready(Err(Or::L(err)));
+                }
+            };
+
+            let wb = stage.filled().len() as u64;
+            this.inner.advance(wb);
+            if wb == 0 {
+                win.pos = next.map_or(win.end, |x| x.max(win.pos + 1)).min(win.end);
+                continue;
+            }
+
+            if win.run_start + win.run.len() as u64 != win.pos {
+                win.run.clear();
+                win.run_start = win.pos;
+            }
+            win.run.extend(stage.filled().iter().cloned());
+            win.pos += wb;
+        }
+
+        let mut win = this.window.take().expect("Read in progress");
+        let run_end = win.run_start + win.run.len() as u64;
+        if !win.run.is_empty() && run_end == win.end {
+            // The buffer may have shrunk since the window was sized, the rest is kept for the next
+            // read
+            let keep = win.run.len() - win.run.len().min(unfilled as usize);
+            let items = &mut win.run[keep..];
+            items.reverse();
+            buf.put_slice(items);
+            *this.cur += items.len() as u64;
+            if keep > 0 {
+                win.end -= items.len() as u64;
+                win.run.truncate(keep);
+                *this.window = Some(win);
+            }
+            return Poll::Ready(Ok(Some(*this.cur)));
+        }
+
+        // A hole, the next data is the highest data below it on the inner reader
+        let next = match win.run.is_empty() {
+            false => Some(len - run_end),
+            true if win.start > 0 => Some(len - win.start),
+            true => None,
+        };
+        Poll::Ready(Ok(next))
+    }
+}
+
+impl<R: AsyncDataRead + AsyncSeek> AsyncSeek for Reverse<R> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.project();
+        *this.window = None;
+        *this.seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let Some(op) = self.seek_op else {
+            return Poll::Ready(Ok(self.cur));
+        };
+
+        let len = match op {
+            SeekFrom::End(_) => ready!(self.as_mut().poll_len(cx))?,
+            _ => 0,
+        };
+
+        let this = self.project();
+        *this.seek_op = None;
+        *this.cur = op.eval(*this.cur, len)?;
+        Poll::Ready(Ok(*this.cur))
+    }
+}
+
+// Only answers once the length is known, either given or found by a read or seek
+impl<R: AsyncDataRead + Provenance> Provenance for Reverse<R> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        let Some(len) = self.len else {
+            return Vec::new();
+        };
+
+        let end = range.end.min(len);
+        if range.start >= end {
+            return Vec::new();
+        }
+
+        let spans = self.reader.provenance(len - end..len - range.start);
+        spans
+            .into_iter()
+            .rev()
+            .map(|(r, path)| (len - r.end..len - r.start, path))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{future::poll_fn, pin::Pin, task::Poll, time::Duration};
+
+    use tokio::time;
+
+    use super::Reverse;
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    #[tokio::test]
+    async fn reverse() {
+        let mut source = OverlayOnce::new([1, 2, 3, 4, 5]).reverse();
+        let mut buf = buf::new::<3, _>();
+        let next = source
+            .read_single_pass(1, &mut buf)
+            .await
+            .expect("Read failed!");
+
+        assert_eq!(next, Some(4));
+        assert_eq!(buf.filled(), &[4, 3, 2]);
+    }
+
+    #[tokio::test]
+    async fn reverse_hole() {
+        // Inner data at 0..2 and 4..6, reversed that is data at 0..2 and 4..6 with a hole at 2..4
+        let mut source = OverlayOnce::new([1, 2])
+            .chain_sized(4, OverlayOnce::new([3, 4]))
+            .reverse();
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(2));
+        assert_eq!(buf.filled(), &[4, 3]);
+
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(2, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(4));
+        assert!(buf.filled().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn smaller_buf_after_pending() {
+        let delay = Duration::from_millis(1);
+        let reader = Box::pin(OverlayOnce::new(vec![1, 2, 3, 4, 5]).delay(delay));
+        let mut source = Reverse::new(reader, Some(5));
+        let mut buf = buf::new::<4, _>();
+        let rs = poll_fn(|cx| Poll::Ready(Pin::new(&mut source).poll_read(cx, &mut buf))).await;
+        assert!(rs.is_pending());
+
+        time::sleep(delay).await;
+        let mut buf = buf::new::<2, _>();
+        let next = poll_fn(|cx| Pin::new(&mut source).poll_read(cx, &mut buf))
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(2));
+        assert_eq!(buf.filled(), &[5, 4]);
+
+        // The rest of the window is handed out without reading it again
+        let mut buf = buf::new::<4, _>();
+        let next = poll_fn(|cx| Pin::new(&mut source).poll_read(cx, &mut buf))
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(4));
+        assert_eq!(buf.filled(), &[3, 2]);
+    }
+}
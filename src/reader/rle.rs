@@ -0,0 +1,246 @@
+use std::{
+    io::{self, SeekFrom},
+    ops::Range,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::io::AsyncSeek;
+
+use super::{
+    provenance::{LayerPath, Provenance},
+    AsyncDataRead,
+};
+use crate::{
+    buf::{DataReadBuf, Staging},
+    or::Or,
+    utils::SeekFromExt,
+};
+
+/// Items read per step while compressing
+const CHUNK: usize = 4096;
+
+/// Runs of repeated items, `None` runs are holes. A read at any position finds its run by binary
+/// search over the run ends
+#[derive(Debug, Clone, Default)]
+pub struct RleSource<T> {
+    runs: Vec<(u64, Option<T>)>,
+    /// End of every run, the start of the next one
+    ends: Vec<u64>,
+    cur: u64,
+}
+
+impl<T> RleSource<T> {
+    pub fn new() -> Self {
+        Self {
+            runs: Vec::new(),
+            ends: Vec::new(),
+            cur: 0,
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.ends.last().copied().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn runs(&self) -> &[(u64, Option<T>)] {
+        &self.runs
+    }
+
+    fn push_run(&mut self, len: u64, item: Option<T>)
+    where
+        T: PartialEq,
+    {
+        if len == 0 {
+            return;
+        }
+
+        let end = self.len() + len;
+        match self.runs.last_mut() {
+            Some(last) if last.1 == item => {
+                last.0 += len;
+                *self.ends.last_mut().expect("One end per run") = end;
+            }
+            _ => {
+                self.runs.push((len, item));
+                self.ends.push(end);
+            }
+        }
+    }
+
+    /// Append `len` copies of `item`, merged into the last run if it has the same item
+    pub fn push(&mut self, len: u64, item: T)
+    where
+        T: PartialEq,
+    {
+        self.push_run(len, Some(item));
+    }
+
+    pub fn push_hole(&mut self, len: u64)
+    where
+        T: PartialEq,
+    {
+        self.push_run(len, None);
+    }
+
+    /// Read `reader` to its end and keep it as runs
+    pub async fn compress<R>(reader: &mut R) -> Result<Self, Or<R::Err, io::Error>>
+    where
+        R: AsyncDataRead<Item = T> + AsyncSeek + Unpin,
+        T: Clone + PartialEq,
+    {
+        let mut rle = Self::new();
+        let mut stage = Staging::default();
+        let mut pos = 0;
+        loop {
+            let mut buf = stage.get(CHUNK);
+            let next = reader.read_single_pass(pos, &mut buf).await?;
+            let data = buf.filled();
+            if data.is_empty() {
+                let Some(next) = next else {
+                    break;
+                };
+
+                rle.push_hole(next - pos);
+                pos = next;
+                continue;
+            }
+
+            for x in data {
+                rle.push(1, x.clone());
+            }
+            pos += data.len() as u64;
+        }
+
+        Ok(rle)
+    }
+}
+
+impl<T: PartialEq> FromIterator<(u64, T)> for RleSource<T> {
+    fn from_iter<I: IntoIterator<Item = (u64, T)>>(iter: I) -> Self {
+        let mut rle = Self::new();
+        for (len, item) in iter {
+            rle.push(len, item);
+        }
+
+        rle
+    }
+}
+
+impl<T: Unpin> AsyncSeek for RleSource<T> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+        this.cur = position.eval(this.cur, this.len())?;
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.cur))
+    }
+}
+
+impl<T: Clone + Unpin> AsyncDataRead for RleSource<T> {
+    type Item = T;
+    type Err = ();
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let this = self.get_mut();
+        let unfilled = (buf.capacity() - buf.filled().len()) as u64;
+        let mut i = this.ends.partition_point(|x| *x <= this.cur);
+        let mut written = 0;
+        while i < this.runs.len() && written < unfilled {
+            let Some(item) = &this.runs[i].1 else {
+                break;
+            };
+
+            let n = (this.ends[i] - this.cur).min(unfilled - written);
+            buf.put_fill(item, n as usize);
+            this.cur += n;
+            written += n;
+            if this.cur == this.ends[i] {
+                i += 1;
+            }
+        }
+
+        let next = match written {
+            0 if i + 1 < this.runs.len() => Some(this.ends[i]),
+            0 => None,
+            _ => Some(this.cur),
+        };
+        Poll::Ready(Ok(next))
+    }
+}
+
+impl<T> Provenance for RleSource<T> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        let mut rs: Vec<(Range<u64>, LayerPath)> = Vec::new();
+        let first = self.ends.partition_point(|x| *x <= range.start);
+        for i in first..self.runs.len() {
+            let start = self.ends[i] - self.runs[i].0;
+            if start >= range.end {
+                break;
+            } else if self.runs[i].1.is_none() {
+                continue;
+            }
+
+            let span = start.max(range.start)..self.ends[i].min(range.end);
+            match rs.last_mut() {
+                Some(last) if last.0.end == span.start => last.0.end = span.end,
+                _ => rs.push((span, Vec::new())),
+            }
+        }
+
+        rs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RleSource;
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    #[tokio::test]
+    async fn runs() {
+        let mut source: RleSource<u8> = [(3, 1), (2, 2), (4, 3)].into_iter().collect();
+        source.push_hole(2);
+        source.push(1, 4);
+        let mut buf = buf::new::<5, _>();
+        let next = source
+            .read_single_pass(1, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(6));
+        assert_eq!(buf.filled(), &[1, 1, 2, 2, 3]);
+
+        let mut buf = buf::new::<5, _>();
+        let next = source
+            .read_single_pass(9, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(11));
+        assert!(buf.filled().is_empty());
+    }
+
+    #[tokio::test]
+    async fn compress() {
+        let mut reader = OverlayOnce::new([7, 7, 7]).chain_sized(5, OverlayOnce::new([7, 8]));
+        let rle = RleSource::compress(&mut reader)
+            .await
+            .expect("Read failed!");
+        assert_eq!(
+            rle.runs(),
+            &[(3, Some(7)), (2, None), (1, Some(7)), (1, Some(8))]
+        );
+    }
+}
@@ -1,68 +1,196 @@
 use std::{
+    io::{self, SeekFrom},
+    ops::Range,
     pin::Pin,
     task::{ready, Context, Poll},
 };
 
-use super::AsyncDataRead;
-use crate::buf::DataReadBuf;
+use super::{
+    provenance::{under, Layer, LayerPath, Provenance},
+    AsyncDataRead,
+};
+use crate::{
+    buf::DataReadBuf,
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
 use pin_project::pin_project;
+use tokio::io::AsyncSeek;
 
 #[derive(Debug, Clone, Copy)]
 #[pin_project]
-pub struct ShiftLeft<S>(#[pin] S, usize);
+pub struct ShiftLeft<S> {
+    #[pin]
+    reader: S,
+    n: u64,
+    inner: InnerCursor,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
 
 impl<S> ShiftLeft<S> {
     pub fn new(data: S, n: usize) -> Self {
-        Self(data, n)
+        Self {
+            reader: data,
+            n: n as u64,
+            inner: InnerCursor::default(),
+            cur: 0,
+            seek_op: None,
+        }
     }
 }
 
-impl<S: AsyncDataRead> AsyncDataRead for ShiftLeft<S> {
+impl<S: AsyncDataRead + AsyncSeek> AsyncDataRead for ShiftLeft<S> {
     type Item = S::Item;
-    type Err = S::Err;
+    type Err = Or<S::Err, io::Error>;
 
     fn poll_read(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         buf: &mut impl DataReadBuf<Item = Self::Item>,
-        pos: usize,
-    ) -> Poll<Result<Option<usize>, Self::Err>> {
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let mut this = self.project();
+        let n = *this.n;
+        ready!(this
+            .inner
+            .poll_seek(this.reader.as_mut(), cx, *this.cur + n))
+        .map_err(Or::R)?;
+        let prev = buf.filled().len();
+        let next = ready!(this.reader.poll_read(cx, buf)).map_err(Or::L)?;
+        let wb = (buf.filled().len() - prev) as u64;
+        this.inner.advance(wb);
+        *this.cur += wb;
+
+        Poll::Ready(Ok(next.map(|x| x.saturating_sub(n))))
+    }
+}
+
+impl<S: AsyncSeek> AsyncSeek for ShiftLeft<S> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        *self.project().seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
         let this = self.project();
-        let poll = ready!(this.0.poll_read(cx, buf, pos + *this.1))?
-            .map(|x| x.checked_sub(*this.1).unwrap_or(0));
+        let Some(op) = *this.seek_op else {
+            return Poll::Ready(Ok(*this.cur));
+        };
+
+        let len = match op {
+            SeekFrom::End(_) => {
+                let len = ready!(this.inner.poll_len(this.reader, cx))?;
+                len.saturating_sub(*this.n)
+            }
+            _ => 0,
+        };
+        *this.seek_op = None;
+        *this.cur = op.eval(*this.cur, len)?;
+        Poll::Ready(Ok(*this.cur))
+    }
+}
 
-        Poll::Ready(Ok(poll))
+impl<S: Provenance> Provenance for ShiftLeft<S> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        let n = self.n;
+        let spans = self
+            .reader
+            .provenance(range.start + n..range.end + n)
+            .into_iter()
+            .map(|(r, path)| (r.start - n..r.end - n, path))
+            .collect();
+
+        under(Layer::ShiftLeft(n), spans)
     }
 }
 
 #[derive(Debug, Clone, Copy)]
 #[pin_project]
-pub struct ShiftRight<S>(#[pin] S, usize);
+pub struct ShiftRight<S> {
+    #[pin]
+    reader: S,
+    n: u64,
+    inner: InnerCursor,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
 
 impl<S> ShiftRight<S> {
     pub fn new(data: S, n: usize) -> Self {
-        Self(data, n)
+        Self {
+            reader: data,
+            n: n as u64,
+            inner: InnerCursor::default(),
+            cur: 0,
+            seek_op: None,
+        }
     }
 }
 
-impl<S: AsyncDataRead> AsyncDataRead for ShiftRight<S> {
+impl<S: AsyncDataRead + AsyncSeek> AsyncDataRead for ShiftRight<S> {
     type Item = S::Item;
-    type Err = S::Err;
+    type Err = Or<S::Err, io::Error>;
 
     fn poll_read(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         buf: &mut impl DataReadBuf<Item = Self::Item>,
-        pos: usize,
-    ) -> Poll<Result<Option<usize>, Self::Err>> {
-        let Some(offset) = pos.checked_sub(self.1) else {
-            return Poll::Ready(Ok(Some(self.1 - pos)));
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let mut this = self.project();
+        let n = *this.n;
+        let Some(offset) = this.cur.checked_sub(n) else {
+            return Poll::Ready(Ok(Some(n)));
         };
 
+        ready!(this.inner.poll_seek(this.reader.as_mut(), cx, offset)).map_err(Or::R)?;
+        let prev = buf.filled().len();
+        let next = ready!(this.reader.poll_read(cx, buf)).map_err(Or::L)?;
+        let wb = (buf.filled().len() - prev) as u64;
+        this.inner.advance(wb);
+        *this.cur += wb;
+
+        Poll::Ready(Ok(next.map(|x| x + n)))
+    }
+}
+
+impl<S: AsyncSeek> AsyncSeek for ShiftRight<S> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        *self.project().seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
         let this = self.project();
-        let poll = ready!(this.0.poll_read(cx, buf, offset))?.map(|x| x + *this.1);
+        let Some(op) = *this.seek_op else {
+            return Poll::Ready(Ok(*this.cur));
+        };
+
+        let len = match op {
+            SeekFrom::End(_) => ready!(this.inner.poll_len(this.reader, cx))? + *this.n,
+            _ => 0,
+        };
+        *this.seek_op = None;
+        *this.cur = op.eval(*this.cur, len)?;
+        Poll::Ready(Ok(*this.cur))
+    }
+}
 
-        Poll::Ready(Ok(poll))
+impl<S: Provenance> Provenance for ShiftRight<S> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        let n = self.n;
+        let start = range.start.max(n);
+        if start >= range.end {
+            return Vec::new();
+        }
+
+        let spans = self
+            .reader
+            .provenance(start - n..range.end - n)
+            .into_iter()
+            .map(|(r, path)| (r.start + n..r.end + n, path))
+            .collect();
+
+        under(Layer::ShiftRight(n), spans)
     }
 }
 
@@ -77,7 +205,7 @@ mod tests {
 
     #[tokio::test]
     async fn left_basic() {
-        let source = OverlayOnce::new(0, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        let source = OverlayOnce::new([1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
         let mut ss = ShiftLeft::new(source, 1);
         let mut buf = buf::new::<3, _>();
         let next = ss
@@ -91,7 +219,7 @@ mod tests {
 
     #[tokio::test]
     async fn left_oob_left() {
-        let source = OverlayOnce::new(10, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        let source = OverlayOnce::new([1, 2, 3, 4, 5, 6, 7, 8, 9, 10]).shift_right(10);
         let mut ss = ShiftLeft::new(source, 1);
         let mut buf = buf::new::<10, _>();
         let next = ss
@@ -105,7 +233,7 @@ mod tests {
 
     #[tokio::test]
     async fn right_basic() {
-        let source = OverlayOnce::new(0, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        let source = OverlayOnce::new([1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
         let mut ss = ShiftRight::new(source, 1);
         let mut buf = buf::new::<3, _>();
         let next = ss
@@ -0,0 +1,374 @@
+use std::{
+    io::{self, SeekFrom},
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use pin_project::pin_project;
+use tokio::io::AsyncSeek;
+
+use super::{stripe::MemberRead, AsyncDataRead};
+use crate::{
+    buf::{DataReadBuf, Staging},
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
+
+/// Most inner items read at once by [`StepBy`]
+const CHUNK: usize = 4096;
+
+fn source_pos(step: u64, phase: u64, pos: u64) -> Option<u64> {
+    pos.checked_mul(step)?.checked_add(phase)
+}
+
+/// Every `step`-th item of a reader starting at `phase`, as a dense reader. Create with
+/// [`AsyncDataRead::step_by`]
+#[derive(Debug)]
+#[pin_project]
+pub struct StepBy<R: AsyncDataRead> {
+    #[pin]
+    reader: R,
+    inner: InnerCursor,
+    step: u64,
+    phase: u64,
+    stage: Staging<R::Item>,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+impl<R: AsyncDataRead> StepBy<R> {
+    pub fn new(reader: R, step: u64, phase: u64) -> Self {
+        assert!(step > 0, "step must be positive!");
+        Self {
+            reader,
+            inner: InnerCursor::default(),
+            step,
+            phase,
+            stage: Staging::default(),
+            cur: 0,
+            seek_op: None,
+        }
+    }
+
+    /// Position of `pos` on the inner reader, `None` if it lies past the last position there is
+    pub fn source_pos(&self, pos: u64) -> Option<u64> {
+        source_pos(self.step, self.phase, pos)
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R> AsyncDataRead for StepBy<R>
+where
+    R: AsyncDataRead + AsyncSeek,
+    R::Item: Clone,
+{
+    type Item = R::Item;
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let mut this = self.project();
+        let (step, phase) = (*this.step, *this.phase);
+        let start = *this.cur;
+        // Hand out what was read so far rather than wait for more
+        let short = |cur: u64| match cur > start {
+            true => Poll::Ready(Ok(Some(cur))),
+            false => Poll::Pending,
+        };
+
+        loop {
+            let unfilled = (buf.capacity() - buf.filled().len()) as u64;
+            if unfilled == 0 {
+                return Poll::Ready(Ok(Some(*this.cur)));
+            }
+
+            // Items that would lie past the last inner position don't exist
+            let Some(pos) = source_pos(step, phase, *this.cur) else {
+                return Poll::Ready(Ok(Some(*this.cur).filter(|x| *x > start)));
+            };
+
+            match this.inner.poll_seek(this.reader.as_mut(), cx, pos) {
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(_)) if *this.cur > start => {
+                    return Poll::Ready(Ok(Some(*this.cur)))
+                }
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(Or::R(err))),
+                Poll::Pending => return short(*this.cur),
+            }
+
+            // One inner read covers the strided items of up to `CHUNK` inner items
+            let items = unfilled.min((CHUNK as u64).div_ceil(step));
+            let mut stage = this.stage.get(((items - 1) * step + 1) as usize);
+            let next = match this.reader.as_mut().poll_read(cx, &mut stage) {
+                Poll::Ready(Ok(next)) => next,
+                // Keep what earlier rounds read, the next read runs into the error again
+                Poll::Ready(Err(_)) if *this.cur > start => {
+                    this.inner.reset();
+                    return Poll::Ready(Ok(Some(*this.cur)));
+                }
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(Or::L(err))),
+                Poll::Pending => return short(*this.cur),
+            };
+            let wb = stage.filled().len() as u64;
+            this.inner.advance(wb);
+            if wb == 0 && *this.cur > start {
+                return Poll::Ready(Ok(Some(*this.cur)));
+            } else if wb == 0 {
+                // The first lane item at or after the next data, it may still be in a hole
+                return Poll::Ready(Ok(next.map(|x| (x.max(pos) - phase).div_ceil(step))));
+            }
+
+            for item in stage.filled().iter().step_by(step as usize) {
+                buf.put_slice(std::slice::from_ref(item));
+            }
+            *this.cur += wb.div_ceil(step);
+            if wb < stage.capacity() as u64 {
+                return Poll::Ready(Ok(Some(*this.cur)));
+            }
+        }
+    }
+}
+
+impl<R: AsyncDataRead + AsyncSeek> AsyncSeek for StepBy<R> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        *self.project().seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.project();
+        let Some(op) = *this.seek_op else {
+            return Poll::Ready(Ok(*this.cur));
+        };
+
+        let len = match op {
+            SeekFrom::End(_) => {
+                let len = ready!(this.inner.poll_len(this.reader, cx))?;
+                len.saturating_sub(*this.phase).div_ceil(*this.step)
+            }
+            _ => 0,
+        };
+        *this.seek_op = None;
+        *this.cur = op.eval(*this.cur, len)?;
+        Poll::Ready(Ok(*this.cur))
+    }
+}
+
+/// Merge lanes item by item, position `p` is item `p / k` of lane `p % k`. Create with
+/// [`interleave`]
+#[derive(Debug)]
+pub struct Interleave<R: AsyncDataRead> {
+    lanes: Vec<R>,
+    cursors: Vec<InnerCursor>,
+    reads: Option<Vec<MemberRead<R::Item>>>,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+/// Merge `lanes` back into one reader, the inverse of [`AsyncDataRead::step_by`] over every phase
+pub fn interleave<R: AsyncDataRead>(lanes: impl IntoIterator<Item = R>) -> Interleave<R> {
+    Interleave::new(lanes.into_iter().collect())
+}
+
+impl<R: AsyncDataRead> Interleave<R> {
+    pub fn new(lanes: Vec<R>) -> Self {
+        assert!(!lanes.is_empty(), "nothing to interleave!");
+        Self {
+            cursors: vec![InnerCursor::default(); lanes.len()],
+            lanes,
+            reads: None,
+            cur: 0,
+            seek_op: None,
+        }
+    }
+
+    pub fn into_inner(self) -> Vec<R> {
+        self.lanes
+    }
+}
+
+impl<R> AsyncDataRead for Interleave<R>
+where
+    R: AsyncDataRead + AsyncSeek + Unpin,
+    R::Item: Clone + Unpin,
+{
+    type Item = R::Item;
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let this = self.get_mut();
+        let k = this.lanes.len() as u64;
+        let cur = this.cur;
+        let unfilled = (buf.capacity() - buf.filled().len()) as u64;
+        if unfilled == 0 {
+            return Poll::Ready(Ok(Some(cur)));
+        }
+
+        // Every lane reads the same rows, one read per lane for the whole buffer
+        let row = cur / k;
+        let rows = (cur + unfilled).div_ceil(k) - row;
+        let reads = this
+            .reads
+            .get_or_insert_with(|| (0..k).map(|_| MemberRead::new(row, rows)).collect());
+
+        let mut pending = false;
+        for (j, rd) in reads.iter_mut().enumerate() {
+            match rd.poll(Pin::new(&mut this.lanes[j]), &mut this.cursors[j], cx) {
+                Poll::Pending => pending = true,
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(err)) => {
+                    this.reads = None;
+                    return Poll::Ready(Err(err));
+                }
+            }
+        }
+
+        if pending {
+            return Poll::Pending;
+        }
+
+        let reads = this.reads.take().expect("Read in progress");
+        let end = cur + unfilled;
+        while this.cur < end {
+            let p = this.cur;
+            let Some(item) = reads[(p % k) as usize]
+                .buf
+                .filled()
+                .get((p / k - row) as usize)
+            else {
+                break;
+            };
+            buf.put_slice(std::slice::from_ref(item));
+            this.cur += 1;
+        }
+
+        if this.cur > cur {
+            return Poll::Ready(Ok(Some(this.cur)));
+        }
+
+        // A lane that read something may have more data right after, otherwise use its next
+        let next = reads
+            .iter()
+            .enumerate()
+            .filter_map(|(j, rd)| {
+                let j = j as u64;
+                let first = if row * k + j >= cur { row } else { row + 1 };
+                match rd.buf.filled().is_empty() {
+                    false => Some(first * k + j),
+                    true => rd.next.flatten().map(|x| x.max(first) * k + j),
+                }
+            })
+            .min();
+        Poll::Ready(Ok(next))
+    }
+}
+
+impl<R> AsyncSeek for Interleave<R>
+where
+    R: AsyncDataRead + AsyncSeek + Unpin,
+    R::Item: Unpin,
+{
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+        this.reads = None;
+        this.seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        let Some(op) = this.seek_op else {
+            return Poll::Ready(Ok(this.cur));
+        };
+
+        let mut len = 0;
+        if let SeekFrom::End(_) = op {
+            let k = this.lanes.len() as u64;
+            for (j, lane) in this.lanes.iter_mut().enumerate() {
+                let lane_len = ready!(this.cursors[j].poll_len(Pin::new(lane), cx))?;
+                if lane_len > 0 {
+                    len = len.max((lane_len - 1) * k + j as u64 + 1);
+                }
+            }
+        }
+
+        this.seek_op = None;
+        this.cur = op.eval(this.cur, len)?;
+        Poll::Ready(Ok(this.cur))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::interleave;
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    #[tokio::test]
+    async fn step_by() {
+        let mut source = OverlayOnce::new([1, 2, 3, 4, 5, 6, 7, 8]).step_by(3, 1);
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+
+        assert_eq!(next, Some(3));
+        assert_eq!(buf.filled(), &[2, 5, 8]);
+    }
+
+    #[tokio::test]
+    async fn huge_step() {
+        let mut source = OverlayOnce::new([1, 2, 3]).step_by(1 << 40, 0);
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+
+        assert_eq!(next, Some(1));
+        assert_eq!(buf.filled(), &[1]);
+
+        let mut source = OverlayOnce::new([1, 2, 3]).step_by(u64::MAX / 2 + 1, 1);
+        let mut buf = buf::new::<10, _>();
+        let next = source.read(0, &mut buf).await.expect("Read failed!");
+        assert_eq!(next, None);
+        assert_eq!(buf.filled(), &[2]);
+
+        // Past the last inner position
+        let next = source
+            .read_single_pass(2, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, None);
+        assert_eq!(source.source_pos(2), None);
+    }
+
+    #[tokio::test]
+    async fn interleave_lanes() {
+        let mut source = interleave([
+            OverlayOnce::new(vec![1, 3, 5]),
+            OverlayOnce::new(vec![2, 4]),
+        ]);
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(1, &mut buf)
+            .await
+            .expect("Read failed!");
+
+        assert_eq!(next, Some(5));
+        assert_eq!(buf.filled(), &[2, 3, 4, 5]);
+    }
+}
@@ -0,0 +1,523 @@
+use std::{
+    io::{self, SeekFrom},
+    mem::MaybeUninit,
+    ops::Range,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use pin_project::pin_project;
+use tokio::io::AsyncSeek;
+
+use super::{
+    provenance::{under, Layer, LayerPath, Provenance},
+    AsyncDataRead,
+};
+use crate::{
+    buf::{DataReadBuf, DataReadBufImpl},
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
+
+/// How logical positions are laid out across the members of a stripe set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StripeLayout {
+    pub members: usize,
+    pub stripe: u64,
+    /// Member position where the striped data starts
+    pub offset: u64,
+}
+
+impl StripeLayout {
+    pub fn new(members: usize, stripe: u64) -> Self {
+        assert!(members > 0 && stripe > 0, "empty stripe layout!");
+        Self {
+            members,
+            stripe,
+            offset: 0,
+        }
+    }
+
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Member holding logical `pos`, the position in that member and how many items are left in
+    /// the stripe
+    pub fn locate(&self, pos: u64) -> (usize, u64, u64) {
+        let n = self.members as u64;
+        let s = pos / self.stripe;
+        let within = pos % self.stripe;
+        let member = (s % n) as usize;
+        let mpos = self.offset + s / n * self.stripe + within;
+        (member, mpos, self.stripe - within)
+    }
+
+    /// Logical position of `pos` in `member`, `None` if it's before the striped data
+    pub fn logical(&self, member: usize, pos: u64) -> Option<u64> {
+        let q = pos.checked_sub(self.offset)?;
+        let s = q / self.stripe * self.members as u64 + member as u64;
+        Some(s * self.stripe + q % self.stripe)
+    }
+
+    /// Length `member` needs to hold the first `len` logical items
+    pub fn member_len(&self, member: usize, len: u64) -> u64 {
+        let n = self.members as u64;
+        let k = member as u64;
+        let full = len / self.stripe;
+        let count = full / n + u64::from(full % n > k);
+        let rem = if full % n == k { len % self.stripe } else { 0 };
+        self.offset + count * self.stripe + rem
+    }
+
+    /// Logical length covered by `member` when it is `len` long
+    pub fn logical_len(&self, member: usize, len: u64) -> u64 {
+        len.checked_sub(1)
+            .and_then(|last| self.logical(member, last))
+            .map(|x| x + 1)
+            .unwrap_or(0)
+    }
+}
+
+type OwnedBuf<T> = DataReadBufImpl<T, Box<[MaybeUninit<T>]>>;
+
+/// A read issued to one member, kept across polls so members can be read concurrently
+#[derive(Debug)]
+pub(super) struct MemberRead<T> {
+    pub pos: u64,
+    pub buf: OwnedBuf<T>,
+    pub next: Option<Option<u64>>,
+}
+
+impl<T> MemberRead<T> {
+    pub fn new(pos: u64, len: u64) -> Self {
+        Self {
+            pos,
+            buf: DataReadBufImpl::new_heap_alloc(len as usize),
+            next: None,
+        }
+    }
+
+    /// Poll the read if it's still in flight, `Ready` once it has completed
+    pub fn poll<R>(
+        &mut self,
+        mut reader: Pin<&mut R>,
+        cursor: &mut InnerCursor,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Or<R::Err, io::Error>>>
+    where
+        R: AsyncDataRead<Item = T> + AsyncSeek,
+        T: Clone,
+    {
+        if self.next.is_some() {
+            return Poll::Ready(Ok(()));
+        }
+
+        ready!(cursor.poll_seek(reader.as_mut(), cx, self.pos)).map_err(Or::R)?;
+        self.next = Some(ready!(reader.poll_read(cx, &mut self.buf)).map_err(Or::L)?);
+        cursor.advance(self.buf.filled().len() as u64);
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Map logical positions onto members of a stripe set (RAID-0). Create with [`Striped::new`]
+#[derive(Debug)]
+pub struct Striped<R: AsyncDataRead> {
+    members: Vec<R>,
+    cursors: Vec<InnerCursor>,
+    layout: StripeLayout,
+    reads: Vec<Option<MemberRead<R::Item>>>,
+    window: Option<Range<u64>>,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+impl<R: AsyncDataRead> Striped<R> {
+    pub fn new(members: Vec<R>, stripe: u64) -> Self {
+        let layout = StripeLayout::new(members.len(), stripe);
+        Self::with_layout(members, layout)
+    }
+
+    pub fn with_layout(members: Vec<R>, layout: StripeLayout) -> Self {
+        assert_eq!(members.len(), layout.members, "member count mismatch!");
+        Self {
+            cursors: vec![InnerCursor::default(); members.len()],
+            reads: (0..members.len()).map(|_| None).collect(),
+            members,
+            layout,
+            window: None,
+            cur: 0,
+            seek_op: None,
+        }
+    }
+
+    pub fn layout(&self) -> StripeLayout {
+        self.layout
+    }
+
+    pub fn into_inner(self) -> Vec<R> {
+        self.members
+    }
+}
+
+impl<R> Striped<R>
+where
+    R: AsyncDataRead + AsyncSeek + Unpin,
+    R::Item: Clone + Unpin,
+{
+    /// One read per member covers all of its stripes in `window`
+    fn start_reads(&mut self, window: Range<u64>) {
+        let mut p = window.start;
+        while p < window.end {
+            let (k, mpos, left) = self.layout.locate(p);
+            let seg = left.min(window.end - p);
+            match &mut self.reads[k] {
+                Some(rd) => {
+                    let want = mpos + seg - rd.pos;
+                    if (rd.buf.capacity() as u64) < want {
+                        rd.buf = DataReadBufImpl::new_heap_alloc(want as usize);
+                    }
+                }
+                rd @ None => *rd = Some(MemberRead::new(mpos, seg)),
+            }
+            p += seg;
+        }
+
+        self.window = Some(window);
+    }
+
+    fn poll_members(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Or<R::Err, io::Error>>> {
+        let mut pending = false;
+        for (k, rd) in self.reads.iter_mut().enumerate() {
+            let Some(rd) = rd else {
+                continue;
+            };
+
+            let member = Pin::new(&mut self.members[k]);
+            match rd.poll(member, &mut self.cursors[k], cx) {
+                Poll::Pending => pending = true,
+                Poll::Ready(rs) => rs?,
+            }
+        }
+
+        if pending {
+            Poll::Pending
+        } else {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    /// Put the member reads back in logical order, up to the first one that came back short
+    fn assemble(&mut self, buf: &mut impl DataReadBuf<Item = R::Item>) -> Option<u64> {
+        let window = self.window.take().expect("No read in progress!");
+        let reads: Vec<_> = self.reads.iter_mut().map(Option::take).collect();
+        // The buffer may have shrunk since the reads were started
+        let unfilled = (buf.capacity() - buf.filled().len()) as u64;
+        let end = window.end.min(window.start + unfilled);
+        let mut p = window.start;
+        while p < end {
+            let (k, mpos, left) = self.layout.locate(p);
+            let seg = left.min(end - p);
+            let rd = reads[k].as_ref().expect("Member was not read!");
+            let filled = rd.buf.filled();
+            let idx = (mpos - rd.pos) as usize;
+            let take = seg.min(filled.len().saturating_sub(idx) as u64);
+            if take > 0 {
+                buf.put_slice(&filled[idx..idx + take as usize]);
+            }
+
+            p += take;
+            if take < seg {
+                break;
+            }
+        }
+
+        let written = p - window.start;
+        self.cur += written;
+        if written > 0 {
+            return Some(self.cur);
+        }
+
+        // Hole at the start of the window
+        let (k, mpos, left) = self.layout.locate(p);
+        let exhausted = reads
+            .iter()
+            .flatten()
+            .all(|rd| rd.buf.filled().is_empty() && rd.next == Some(None));
+        match reads[k].as_ref().and_then(|rd| rd.next.flatten()) {
+            Some(x) if x < mpos + left => Some(p + x - mpos),
+            _ if exhausted => None,
+            _ => Some(p + left),
+        }
+    }
+}
+
+impl<R> AsyncDataRead for Striped<R>
+where
+    R: AsyncDataRead + AsyncSeek + Unpin,
+    R::Item: Clone + Unpin,
+{
+    type Item = R::Item;
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let this = self.get_mut();
+        if this.window.is_none() {
+            let unfilled = (buf.capacity() - buf.filled().len()) as u64;
+            this.start_reads(this.cur..this.cur + unfilled);
+        }
+
+        if let Err(err) = ready!(this.poll_members(cx)) {
+            this.window = None;
+            this.reads.iter_mut().for_each(|x| *x = None);
+            return Poll::Ready(Err(err));
+        }
+
+        Poll::Ready(Ok(this.assemble(buf)))
+    }
+}
+
+impl<R> AsyncSeek for Striped<R>
+where
+    R: AsyncDataRead + AsyncSeek + Unpin,
+    R::Item: Unpin,
+{
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+        this.window = None;
+        this.reads.iter_mut().for_each(|x| *x = None);
+        this.seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        let Some(op) = this.seek_op else {
+            return Poll::Ready(Ok(this.cur));
+        };
+
+        let mut len = 0;
+        if let SeekFrom::End(_) = op {
+            for (k, member) in this.members.iter_mut().enumerate() {
+                let member_len = ready!(this.cursors[k].poll_len(Pin::new(member), cx))?;
+                len = len.max(this.layout.logical_len(k, member_len));
+            }
+        }
+
+        this.seek_op = None;
+        this.cur = op.eval(this.cur, len)?;
+        Poll::Ready(Ok(this.cur))
+    }
+}
+
+impl<R: AsyncDataRead + Provenance> Provenance for Striped<R> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        let mut rs = Vec::new();
+        let mut p = range.start;
+        while p < range.end {
+            let (k, mpos, left) = self.layout.locate(p);
+            let seg = left.min(range.end - p);
+            let spans = self.members[k]
+                .provenance(mpos..mpos + seg)
+                .into_iter()
+                .map(|(r, path)| (r.start - mpos + p..r.end - mpos + p, path))
+                .collect();
+            rs.extend(under(Layer::Member(k), spans));
+            p += seg;
+        }
+
+        rs
+    }
+}
+
+/// One member of a stripe set, read out of the logical reader. Create with
+/// [`AsyncDataRead::stripe_member`]
+#[derive(Debug)]
+#[pin_project]
+pub struct StripeMember<R> {
+    #[pin]
+    reader: R,
+    inner: InnerCursor,
+    layout: StripeLayout,
+    member: usize,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+impl<R> StripeMember<R> {
+    pub fn new(reader: R, layout: StripeLayout, member: usize) -> Self {
+        assert!(member < layout.members, "member out of bound!");
+        Self {
+            reader,
+            inner: InnerCursor::default(),
+            layout,
+            member,
+            cur: 0,
+            seek_op: None,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R> AsyncDataRead for StripeMember<R>
+where
+    R: AsyncDataRead + AsyncSeek,
+{
+    type Item = R::Item;
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let mut this = self.project();
+        let layout = *this.layout;
+        let cur = *this.cur;
+        let Some(logical) = layout.logical(*this.member, cur) else {
+            // Nothing of the stripe set lives before the offset
+            return Poll::Ready(Ok(Some(layout.offset)));
+        };
+
+        ready!(this.inner.poll_seek(this.reader.as_mut(), cx, logical)).map_err(Or::R)?;
+        let left = layout.stripe - (cur - layout.offset) % layout.stripe;
+        let unfilled = (buf.capacity() - buf.filled().len()) as u64;
+        let mut new_buf = buf.take(unfilled.min(left) as usize);
+        let next = ready!(this.reader.poll_read(cx, &mut new_buf)).map_err(Or::L)?;
+        let wb = new_buf.filled().len() as u64;
+        this.inner.advance(wb);
+        if wb > 0 {
+            *this.cur += wb;
+            return Poll::Ready(Ok(Some(*this.cur)));
+        }
+
+        let next = match next {
+            Some(x) if x < logical + left => cur + x - logical,
+            Some(_) => cur + left,
+            None => return Poll::Ready(Ok(None)),
+        };
+        Poll::Ready(Ok(Some(next)))
+    }
+}
+
+impl<R: AsyncSeek> AsyncSeek for StripeMember<R> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        *self.project().seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.project();
+        let Some(op) = *this.seek_op else {
+            return Poll::Ready(Ok(*this.cur));
+        };
+
+        let len = match op {
+            SeekFrom::End(_) => {
+                let len = ready!(this.inner.poll_len(this.reader, cx))?;
+                this.layout.member_len(*this.member, len)
+            }
+            _ => 0,
+        };
+        *this.seek_op = None;
+        *this.cur = op.eval(*this.cur, len)?;
+        Poll::Ready(Ok(*this.cur))
+    }
+}
+
+impl<R: Provenance> Provenance for StripeMember<R> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        let layout = self.layout;
+        let mut rs = Vec::new();
+        let mut q = range.start.max(layout.offset);
+        while q < range.end {
+            let logical = layout
+                .logical(self.member, q)
+                .expect("Position is after the offset");
+            let left = layout.stripe - (q - layout.offset) % layout.stripe;
+            let seg = left.min(range.end - q);
+            let spans = self.reader.provenance(logical..logical + seg);
+            rs.extend(
+                spans
+                    .into_iter()
+                    .map(|(r, path)| (r.start - logical + q..r.end - logical + q, path)),
+            );
+            q += seg;
+        }
+
+        rs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{future::poll_fn, pin::Pin, task::Poll, time::Duration};
+
+    use tokio::time;
+
+    use super::{StripeLayout, Striped};
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    #[tokio::test]
+    async fn striped() {
+        let members = vec![
+            OverlayOnce::new(vec![1, 2, 5, 6]),
+            OverlayOnce::new(vec![3, 4, 7, 8]),
+        ];
+        let mut source = Striped::new(members, 2);
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(1, &mut buf)
+            .await
+            .expect("Read failed!");
+
+        assert_eq!(next, Some(8));
+        assert_eq!(buf.filled(), &[2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn smaller_buf_after_pending() {
+        let delay = Duration::from_millis(1);
+        let members = vec![
+            Box::pin(OverlayOnce::new(vec![1, 2, 5, 6]).delay(delay)),
+            Box::pin(OverlayOnce::new(vec![3, 4, 7, 8]).delay(delay)),
+        ];
+        let mut source = Striped::new(members, 2);
+        let mut buf = buf::new::<10, _>();
+        let rs = poll_fn(|cx| Poll::Ready(Pin::new(&mut source).poll_read(cx, &mut buf))).await;
+        assert!(rs.is_pending());
+
+        time::sleep(delay).await;
+        let mut buf = buf::new::<3, _>();
+        let next = poll_fn(|cx| Pin::new(&mut source).poll_read(cx, &mut buf))
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(3));
+        assert_eq!(buf.filled(), &[1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn member() {
+        let layout = StripeLayout::new(2, 2);
+        let mut source = OverlayOnce::new([1, 2, 3, 4, 5, 6, 7, 8]).stripe_member(layout, 1);
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(1, &mut buf)
+            .await
+            .expect("Read failed!");
+
+        assert_eq!(next, Some(2));
+        assert_eq!(buf.filled(), &[4]);
+    }
+}
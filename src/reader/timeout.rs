@@ -0,0 +1,196 @@
+use std::{
+    future::Future,
+    io::{self, SeekFrom},
+    ops::Range,
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use pin_project::pin_project;
+use tokio::{
+    io::AsyncSeek,
+    time::{self, Instant, Sleep},
+};
+
+use super::{
+    provenance::{LayerPath, Provenance},
+    AsyncDataRead,
+};
+use crate::{
+    buf::{DataReadBuf, Staging},
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
+
+/// A read at position `pos` took too long
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedOut {
+    pub pos: u64,
+}
+
+/// Fail reads that don't complete in time. Create with [`AsyncDataRead::timeout`] or
+/// [`AsyncDataRead::deadline`]
+///
+/// Like [`tokio::time::timeout`] the inner reader is polled before the timer, so a read that is
+/// ready is never failed. The inner reader reads into scratch space, a timed out read leaves the
+/// caller's buffer as it was.
+#[derive(Debug)]
+#[pin_project]
+pub struct Timeout<R: AsyncDataRead> {
+    #[pin]
+    reader: R,
+    inner: InnerCursor,
+    per_read: Option<Duration>,
+    deadline: Option<Instant>,
+    /// Armed for the read in progress
+    #[pin]
+    timer: Option<Sleep>,
+    stage: Staging<R::Item>,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+impl<R: AsyncDataRead> Timeout<R> {
+    /// Every read has `per_read` to complete and none can complete after `deadline`
+    pub fn new(reader: R, per_read: Option<Duration>, deadline: Option<Instant>) -> Self {
+        Self {
+            reader,
+            inner: InnerCursor::default(),
+            per_read,
+            deadline,
+            timer: None,
+            stage: Staging::default(),
+            cur: 0,
+            seek_op: None,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncDataRead + AsyncSeek> AsyncDataRead for Timeout<R>
+where
+    R::Item: Clone,
+{
+    type Item = R::Item;
+    type Err = Or<Or<R::Err, TimedOut>, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let mut this = self.project();
+        let cur = *this.cur;
+        if this.timer.is_none() {
+            let expiry = this.per_read.map(|x| Instant::now() + x);
+            let expiry = match (expiry, *this.deadline) {
+                (Some(x), Some(y)) => Some(x.min(y)),
+                (x, y) => x.or(y),
+            };
+            this.timer.set(expiry.map(time::sleep_until));
+        }
+
+        if let Poll::Ready(rs) = this.inner.poll_seek(this.reader.as_mut(), cx, cur) {
+            if let Err(err) = rs {
+                this.timer.set(None);
+                return Poll::Ready(Err(Or::R(err)));
+            }
+
+            let unfilled = buf.capacity() - buf.filled().len();
+            let mut stage = this.stage.get(unfilled);
+            if let Poll::Ready(rs) = this.reader.as_mut().poll_read(cx, &mut stage) {
+                this.timer.set(None);
+                let next = rs.map_err(|x| Or::L(Or::L(x)))?;
+                let data = stage.filled();
+                this.inner.advance(data.len() as u64);
+                *this.cur += data.len() as u64;
+                buf.put_slice(data);
+                return Poll::Ready(Ok(next));
+            }
+        }
+
+        if let Some(timer) = this.timer.as_mut().as_pin_mut() {
+            ready!(timer.poll(cx));
+            this.timer.set(None);
+            // Whatever the inner reader was doing is abandoned, seek it again next time
+            this.inner.reset();
+            return Poll::Ready(Err(Or::L(Or::R(TimedOut { pos: cur }))));
+        }
+
+        Poll::Pending
+    }
+}
+
+impl<R: AsyncDataRead + AsyncSeek> AsyncSeek for Timeout<R> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let mut this = self.project();
+        this.timer.set(None);
+        *this.seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.project();
+        let Some(op) = *this.seek_op else {
+            return Poll::Ready(Ok(*this.cur));
+        };
+
+        let len = match op {
+            SeekFrom::End(_) => ready!(this.inner.poll_len(this.reader, cx))?,
+            _ => 0,
+        };
+        *this.seek_op = None;
+        *this.cur = op.eval(*this.cur, len)?;
+        Poll::Ready(Ok(*this.cur))
+    }
+}
+
+impl<R: AsyncDataRead + Provenance> Provenance for Timeout<R> {
+    fn provenance(&self, range: Range<u64>) -> Vec<(Range<u64>, LayerPath)> {
+        self.reader.provenance(range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use super::TimedOut;
+    use crate::{
+        buf::{self, DataReadBuf},
+        or::Or,
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn timeout() {
+        let source = OverlayOnce::new([1, 2, 3, 4])
+            .delay(Duration::from_secs(10))
+            .timeout(Duration::from_millis(10));
+        tokio::pin!(source);
+        let start = Instant::now();
+        let mut buf = buf::new::<4, _>();
+        let rs = source.read_single_pass(1, &mut buf).await;
+        let rs = rs.map_err(Or::flatten);
+        assert!(matches!(rs, Err(Or::L(Or::R(TimedOut { pos: 1 })))));
+        assert!(buf.filled().is_empty());
+        assert_eq!(start.elapsed(), Duration::from_millis(10));
+
+        let source = OverlayOnce::new([1, 2, 3, 4])
+            .delay(Duration::from_millis(1))
+            .timeout(Duration::from_secs(10));
+        tokio::pin!(source);
+        let next = source
+            .read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(4));
+        assert_eq!(buf.filled(), &[1, 2, 3, 4]);
+    }
+}
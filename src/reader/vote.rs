@@ -0,0 +1,306 @@
+use std::{
+    io::{self, SeekFrom},
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use tokio::io::AsyncSeek;
+
+use super::{stripe::MemberRead, AsyncDataRead};
+use crate::{
+    buf::DataReadBuf,
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
+
+/// A position where the replicas did not all agree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dispute<T> {
+    pub pos: u64,
+    /// What every replica had at `pos`, `None` for a hole
+    pub votes: Vec<Option<T>>,
+}
+
+/// Read every range from all replicas and return the item most of them agree on, where a hole
+/// counts as an item too. Create with [`Vote::new`]
+#[derive(Debug)]
+pub struct Vote<R: AsyncDataRead> {
+    replicas: Vec<R>,
+    cursors: Vec<InnerCursor>,
+    reads: Option<Vec<MemberRead<R::Item>>>,
+    disputes: Vec<Dispute<R::Item>>,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+impl<R: AsyncDataRead> Vote<R> {
+    pub fn new(replicas: Vec<R>) -> Self {
+        assert!(!replicas.is_empty(), "nothing to vote on!");
+        Self {
+            cursors: vec![InnerCursor::default(); replicas.len()],
+            replicas,
+            reads: None,
+            disputes: Vec::new(),
+            cur: 0,
+            seek_op: None,
+        }
+    }
+
+    /// Positions read so far where the replicas disagreed, in read order
+    pub fn disputes(&self) -> &[Dispute<R::Item>] {
+        &self.disputes
+    }
+
+    pub fn take_disputes(&mut self) -> Vec<Dispute<R::Item>> {
+        std::mem::take(&mut self.disputes)
+    }
+
+    pub fn into_inner(self) -> Vec<R> {
+        self.replicas
+    }
+}
+
+/// Index of the vote with the most equal votes, ties go to the earliest replica. A hole is a vote
+/// like any other, so replicas that agree on a hole outvote a single one with data
+fn majority<T: Eq>(votes: &[Option<&T>]) -> usize {
+    let count = |x: &Option<&T>| votes.iter().filter(|v| *v == x).count();
+    votes
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (i, count(v)))
+        .fold(
+            (0, 0),
+            |best, (i, c)| if c > best.1 { (i, c) } else { best },
+        )
+        .0
+}
+
+impl<R> AsyncDataRead for Vote<R>
+where
+    R: AsyncDataRead + AsyncSeek + Unpin,
+    R::Item: Clone + Eq + Unpin,
+{
+    type Item = R::Item;
+    type Err = Or<R::Err, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let this = self.get_mut();
+        let cur = this.cur;
+        let unfilled = (buf.capacity() - buf.filled().len()) as u64;
+        let reads = this.reads.get_or_insert_with(|| {
+            (0..this.replicas.len())
+                .map(|_| MemberRead::new(cur, unfilled))
+                .collect()
+        });
+
+        let mut pending = false;
+        for (k, rd) in reads.iter_mut().enumerate() {
+            let replica = Pin::new(&mut this.replicas[k]);
+            match rd.poll(replica, &mut this.cursors[k], cx) {
+                Poll::Pending => pending = true,
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(err)) => {
+                    this.reads = None;
+                    return Poll::Ready(Err(err));
+                }
+            }
+        }
+
+        if pending {
+            return Poll::Pending;
+        }
+
+        let reads = this.reads.take().expect("Read in progress");
+        let longest = reads
+            .iter()
+            .map(|x| x.buf.filled().len())
+            .max()
+            .unwrap_or(0);
+        if longest == 0 {
+            // Data may come back as soon as any replica has some
+            let next = reads.iter().filter_map(|x| x.next.flatten()).min();
+            return Poll::Ready(Ok(next));
+        }
+
+        // A short read says nothing about what comes after it, only a hole is a vote for `None`
+        let covered = reads
+            .iter()
+            .map(|x| match x.buf.filled().len() {
+                0 => x
+                    .next
+                    .flatten()
+                    .map_or(usize::MAX, |n| n.saturating_sub(cur) as usize),
+                len => len,
+            })
+            .min()
+            .unwrap_or(0)
+            .max(1);
+        // The buffer may have shrunk since the reads were started
+        let n = longest.min(covered).min(unfilled as usize);
+        let (mut written, mut hole) = (0, 0);
+        for i in 0..n {
+            let votes: Vec<_> = reads.iter().map(|x| x.buf.filled().get(i)).collect();
+            let win = votes[majority(&votes)];
+            // A read either hands out data or reports a hole, the rest is left for the next one
+            match win {
+                Some(_) if hole > 0 => break,
+                None if written > 0 => break,
+                _ => {}
+            }
+
+            if votes.iter().any(|x| *x != win) {
+                this.disputes.push(Dispute {
+                    pos: cur + i as u64,
+                    votes: votes.iter().map(|x| x.cloned()).collect(),
+                });
+            }
+
+            match win {
+                Some(x) => {
+                    buf.put_slice(std::slice::from_ref(x));
+                    written += 1;
+                }
+                None => hole += 1,
+            }
+        }
+
+        if hole > 0 {
+            return Poll::Ready(Ok(Some(cur + hole)));
+        }
+
+        this.cur += written;
+        Poll::Ready(Ok(Some(this.cur)))
+    }
+}
+
+impl<R> AsyncSeek for Vote<R>
+where
+    R: AsyncDataRead + AsyncSeek + Unpin,
+    R::Item: Unpin,
+{
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+        this.reads = None;
+        this.seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        let Some(op) = this.seek_op else {
+            return Poll::Ready(Ok(this.cur));
+        };
+
+        let mut len = 0;
+        if let SeekFrom::End(_) = op {
+            for (k, replica) in this.replicas.iter_mut().enumerate() {
+                len = len.max(ready!(this.cursors[k].poll_len(Pin::new(replica), cx))?);
+            }
+        }
+
+        this.seek_op = None;
+        this.cur = op.eval(this.cur, len)?;
+        Poll::Ready(Ok(this.cur))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dispute, Vote};
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    #[tokio::test]
+    async fn majority() {
+        let mut source = Vote::new(vec![
+            OverlayOnce::new(vec![1, 2, 3, 4]),
+            OverlayOnce::new(vec![1, 9, 3, 4]),
+            OverlayOnce::new(vec![1, 2, 3]),
+        ]);
+        let mut buf = buf::new::<10, _>();
+        let next = source.read(0, &mut buf).await.expect("Read failed!");
+
+        assert_eq!(next, None);
+        assert_eq!(buf.filled(), &[1, 2, 3, 4]);
+        assert_eq!(
+            source.disputes(),
+            &[
+                Dispute {
+                    pos: 1,
+                    votes: vec![Some(2), Some(9), Some(2)],
+                },
+                Dispute {
+                    pos: 3,
+                    votes: vec![Some(4), Some(4), None],
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn short_read() {
+        let replica = |a: Vec<i32>, b: Vec<i32>| {
+            let n = a.len() as u64;
+            OverlayOnce::new(a).chain_sized(n, OverlayOnce::new(b))
+        };
+        let mut source = Vote::new(vec![
+            replica(vec![1, 2], vec![3, 4]),
+            replica(vec![1, 2, 3, 4], vec![]),
+            replica(vec![1, 2, 3, 5], vec![]),
+        ]);
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(0, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(2));
+        assert_eq!(buf.filled(), &[1, 2]);
+        assert!(source.disputes().is_empty());
+
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(2, &mut buf)
+            .await
+            .expect("Read failed!");
+        assert_eq!(next, Some(4));
+        assert_eq!(buf.filled(), &[3, 4]);
+        assert_eq!(
+            source.disputes(),
+            &[Dispute {
+                pos: 3,
+                votes: vec![Some(4), Some(4), Some(5)],
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn hole_majority() {
+        let replica = |start: u64, data: Vec<i32>| {
+            OverlayOnce::new(vec![1]).chain_sized(start, OverlayOnce::new(data))
+        };
+        let mut source = Vote::new(vec![
+            replica(2, vec![3]),
+            replica(3, vec![]),
+            replica(1, vec![7, 8]),
+        ]);
+        let mut buf = buf::new::<10, _>();
+        let next = source.read(0, &mut buf).await.expect("Read failed!");
+
+        // Two replicas agree there is nothing at 1
+        assert_eq!(next, Some(2));
+        assert_eq!(buf.filled(), &[1]);
+        assert_eq!(
+            source.disputes(),
+            &[Dispute {
+                pos: 1,
+                votes: vec![None, None, Some(7)],
+            }]
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use std::{
+    io::{self, SeekFrom},
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use pin_project::pin_project;
+use tokio::io::AsyncSeek;
+
+use super::{stripe::MemberRead, AsyncDataRead};
+use crate::{
+    buf::DataReadBuf,
+    or::Or,
+    utils::{InnerCursor, SeekFromExt},
+};
+
+/// Pair up the items of two readers at the same position. Create with [`AsyncDataRead::zip`]
+#[derive(Debug)]
+#[pin_project]
+pub struct Zip<A: AsyncDataRead, B: AsyncDataRead> {
+    #[pin]
+    a: A,
+    #[pin]
+    b: B,
+    a_cur: InnerCursor,
+    b_cur: InnerCursor,
+    reads: Option<(MemberRead<A::Item>, MemberRead<B::Item>)>,
+    cur: u64,
+    seek_op: Option<SeekFrom>,
+}
+
+impl<A: AsyncDataRead, B: AsyncDataRead> Zip<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self {
+            a,
+            b,
+            a_cur: InnerCursor::default(),
+            b_cur: InnerCursor::default(),
+            reads: None,
+            cur: 0,
+            seek_op: None,
+        }
+    }
+
+    pub fn into_inner(self) -> (A, B) {
+        (self.a, self.b)
+    }
+}
+
+impl<A, B> AsyncDataRead for Zip<A, B>
+where
+    A: AsyncDataRead + AsyncSeek,
+    B: AsyncDataRead + AsyncSeek,
+    A::Item: Clone,
+    B::Item: Clone,
+{
+    type Item = (A::Item, B::Item);
+    type Err = Or<Or<A::Err, B::Err>, io::Error>;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut impl DataReadBuf<Item = Self::Item>,
+    ) -> Poll<Result<Option<u64>, Self::Err>> {
+        let this = self.project();
+        let cur = *this.cur;
+        let unfilled = (buf.capacity() - buf.filled().len()) as u64;
+        let (ra, rb) = this.reads.get_or_insert_with(|| {
+            (
+                MemberRead::new(cur, unfilled),
+                MemberRead::new(cur, unfilled),
+            )
+        });
+
+        // Both reads are in flight at once, an error drops the other one
+        let pa = ra.poll(this.a, this.a_cur, cx).map_err(|x| x.map_l(Or::L));
+        let pb = rb.poll(this.b, this.b_cur, cx).map_err(|x| x.map_l(Or::R));
+        let rs = match (pa, pb) {
+            (Poll::Ready(Err(err)), _) | (_, Poll::Ready(Err(err))) => Err(err),
+            (Poll::Ready(Ok(())), Poll::Ready(Ok(()))) => Ok(()),
+            _ => return Poll::Pending,
+        };
+
+        let (ra, rb) = this.reads.take().expect("Read in progress");
+        rs?;
+
+        let (fa, fb) = (ra.buf.filled(), rb.buf.filled());
+        let n = fa.len().min(fb.len());
+        if n > 0 {
+            // The buffer may have shrunk since the reads were started
+            let n = n.min(unfilled as usize);
+            let items: Vec<_> = fa[..n]
+                .iter()
+                .cloned()
+                .zip(fb[..n].iter().cloned())
+                .collect();
+            buf.put_slice(&items);
+            *this.cur += n as u64;
+            return Poll::Ready(Ok(Some(*this.cur)));
+        }
+
+        // Both need data, so the hole lasts until the later of the two reports some
+        let next = [(fa.is_empty(), ra.next), (fb.is_empty(), rb.next)]
+            .into_iter()
+            .filter(|x| x.0)
+            .map(|x| x.1.flatten())
+            .try_fold(cur, |acc, x| x.map(|x| acc.max(x)));
+        Poll::Ready(Ok(next))
+    }
+}
+
+impl<A, B> AsyncSeek for Zip<A, B>
+where
+    A: AsyncDataRead + AsyncSeek,
+    B: AsyncDataRead + AsyncSeek,
+{
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.project();
+        *this.reads = None;
+        *this.seek_op = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.project();
+        let Some(op) = *this.seek_op else {
+            return Poll::Ready(Ok(*this.cur));
+        };
+
+        let len = match op {
+            SeekFrom::End(_) => {
+                let a = ready!(this.a_cur.poll_len(this.a, cx))?;
+                let b = ready!(this.b_cur.poll_len(this.b, cx))?;
+                a.min(b)
+            }
+            _ => 0,
+        };
+        *this.seek_op = None;
+        *this.cur = op.eval(*this.cur, len)?;
+        Poll::Ready(Ok(*this.cur))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        buf::{self, DataReadBuf},
+        reader::{overlay_once::OverlayOnce, AsyncDataRead},
+    };
+
+    #[tokio::test]
+    async fn zip() {
+        let mut source = OverlayOnce::new([1, 2, 3, 4]).zip(OverlayOnce::new([5, 6, 7]));
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(1, &mut buf)
+            .await
+            .expect("Read failed!");
+
+        assert_eq!(next, Some(3));
+        assert_eq!(buf.filled(), &[(2, 6), (3, 7)]);
+    }
+
+    #[tokio::test]
+    async fn zip_hole() {
+        let mut source = OverlayOnce::new([1, 2, 3, 4])
+            .zip(OverlayOnce::new([5]).chain_sized(3, OverlayOnce::new([6])));
+        let mut buf = buf::new::<10, _>();
+        let next = source
+            .read_single_pass(1, &mut buf)
+            .await
+            .expect("Read failed!");
+
+        assert_eq!(next, Some(3));
+        assert!(buf.filled().is_empty());
+    }
+}
@@ -1,9 +1,11 @@
 use std::{
-    io::{self, ErrorKind, Seek, SeekFrom},
-    task::{Context, Poll},
+    io::{self, ErrorKind, SeekFrom},
+    pin::Pin,
+    task::{ready, Context, Poll},
 };
 
 use extension_trait::extension_trait;
+use tokio::io::AsyncSeek;
 
 #[extension_trait]
 pub impl SeekFromExt for SeekFrom {
@@ -14,7 +16,7 @@ pub impl SeekFromExt for SeekFrom {
                 if x > 0 {
                     len + (x as u64)
                 } else {
-                    len.checked_sub(x.abs() as u64).ok_or_else(|| {
+                    len.checked_sub(x.unsigned_abs()).ok_or_else(|| {
                         io::Error::new(ErrorKind::InvalidInput, "Input underflow!")
                     })?
                 }
@@ -23,7 +25,7 @@ pub impl SeekFromExt for SeekFrom {
                 if x > 0 {
                     cur + (x as u64)
                 } else {
-                    cur.checked_sub(x.abs() as u64).ok_or_else(|| {
+                    cur.checked_sub(x.unsigned_abs()).ok_or_else(|| {
                         io::Error::new(ErrorKind::InvalidInput, "Input underflow!")
                     })?
                 }
@@ -37,5 +39,109 @@ pub impl SeekFromExt for SeekFrom {
 #[inline]
 pub fn reschedule<T>(cx: &mut Context<'_>) -> Poll<T> {
     cx.waker().wake_by_ref();
-    return Poll::Pending;
+    Poll::Pending
+}
+
+/// Where a combinator left one of its inner readers, so it only seeks the reader when it has to
+#[derive(Debug, Default, Clone, Copy)]
+pub struct InnerCursor {
+    pos: Option<u64>,
+    seeking: Option<SeekFrom>,
+}
+
+impl InnerCursor {
+    fn poll_pending<R: AsyncSeek + ?Sized>(
+        &mut self,
+        reader: Pin<&mut R>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<u64>> {
+        let rs = ready!(reader.poll_complete(cx));
+        self.pos = match (self.seeking.take(), &rs) {
+            (_, Err(_)) => None,
+            (Some(SeekFrom::Start(x)), Ok(_)) => Some(x),
+            (_, Ok(x)) => Some(*x),
+        };
+        Poll::Ready(rs)
+    }
+
+    /// Move `reader` to `pos` and wait for the seek to complete.
+    ///
+    /// We always seek to an absolute position we have computed ourselves, so the position the
+    /// reader reports is not checked. A failed seek leaves the position unknown.
+    pub fn poll_seek<R: AsyncSeek + ?Sized>(
+        &mut self,
+        mut reader: Pin<&mut R>,
+        cx: &mut Context<'_>,
+        pos: u64,
+    ) -> Poll<io::Result<()>> {
+        loop {
+            if self.seeking.is_some() {
+                ready!(self.poll_pending(reader.as_mut(), cx))?;
+            }
+
+            if self.pos == Some(pos) {
+                return Poll::Ready(Ok(()));
+            }
+
+            self.pos = None;
+            reader.as_mut().start_seek(SeekFrom::Start(pos))?;
+            self.seeking = Some(SeekFrom::Start(pos));
+        }
+    }
+
+    /// Length of `reader`, found by seeking to its end
+    pub fn poll_len<R: AsyncSeek + ?Sized>(
+        &mut self,
+        mut reader: Pin<&mut R>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<u64>> {
+        if self.seeking != Some(SeekFrom::End(0)) {
+            if self.seeking.is_some() {
+                ready!(self.poll_pending(reader.as_mut(), cx))?;
+            }
+
+            self.pos = None;
+            reader.as_mut().start_seek(SeekFrom::End(0))?;
+            self.seeking = Some(SeekFrom::End(0));
+        }
+
+        self.poll_pending(reader, cx)
+    }
+
+    /// The reader has consumed `n` items since the last seek
+    pub fn advance(&mut self, n: u64) {
+        if let Some(pos) = &mut self.pos {
+            *pos += n;
+        }
+    }
+
+    /// Forget the position, the next [`poll_seek`](Self::poll_seek) always seeks
+    pub fn reset(&mut self) {
+        self.pos = None;
+    }
+}
+
+/// Small seeded generator for jitter, the same seed always gives the same numbers
+#[derive(Debug, Clone, Copy)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
 }